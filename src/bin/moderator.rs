@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use scumsim::game::{Game, PlayerId, Setup};
+use scumsim::components::*;
+use scumsim::components::actions::ActionKind;
+use scumsim::resources::Phase;
+use scumsim::catalogue::{Catalogue, cause_key};
+use scumsim::roles::{RoleDefinition, load_definitions};
+use scumsim::validation::ActionError;
+
+const HELP: &str = "\
+Setup:
+  add <name> <role> [faction] [modifier...]  add a player
+  save <file> | load <file>                  save or continue a game
+  catalogue <file>                           load message templates, e.g. a translation
+  roles <file>                               load role definitions for 'add'
+Night:
+  act <player> <action> [target]             submit a player's night action
+  preview                                    show submitted actions and conflicts
+  resolve                                    resolve the night
+  results [player]                           show night results
+  messages [player]                          show private messages sent to players
+  write <dir>                                write last night's private messages to files
+  graph <dot|json> [file]                    export last night's visits and kills
+Day:
+  vote <voter> <target> | unvote <voter>     record a day vote
+  tally                                      show the vote count
+  lynch [player]                             lynch the named player, or the vote leader
+  nolynch                                    end the day without a lynch
+  confirm <player>                           reveal an Innocent Child
+  shoot <player> <target>                    shoot a player with a gun
+Anytime:
+  players                                    list players
+  role <role>                                describe a role
+  abilities <player>                         list a player's abilities and uses left
+  help | quit";
+
+/// A moderator's interactive session, with day votes kept until the day ends
+struct Moderator {
+    game: Game,
+    votes: HashMap<PlayerId, PlayerId>,
+    definitions: Vec<RoleDefinition>,
+}
+
+fn main() {
+    env_logger::init();
+
+    let mut moderator = Moderator {
+        game: Game::new(Setup::default()),
+        votes: HashMap::new(),
+        definitions: Vec::new(),
+    };
+    println!("scumsim moderator, type 'help' for commands");
+
+    let stdin = io::stdin();
+    loop {
+        print!("{}> ", moderator.prompt());
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => (),
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => continue,
+            ["quit"] | ["exit"] => break,
+            words => {
+                if let Err(msg) = moderator.run(words) {
+                    println!("error: {}", msg);
+                }
+            },
+        }
+    }
+}
+
+impl Moderator {
+    fn prompt(&self) -> String {
+        let state = self.game.state();
+        if state.game_over {
+            return String::from("game over");
+        }
+        match state.phase {
+            Phase::Day   => format!("day {}", state.night.0),
+            Phase::Night => format!("night {}", state.night.0),
+        }
+    }
+
+    fn run(&mut self, words: &[&str]) -> Result<(), String> {
+        match words {
+            ["help"] => println!("{}", HELP),
+            ["players"] => self.list_players(),
+            ["abilities", player] => {
+                let player = self.find(player)?;
+                for kind in self.game.actions_of(player) {
+                    match self.game.charges_left(player, kind) {
+                        Some(uses) => println!("  {:?} ({} left)", kind, uses),
+                        None       => println!("  {:?}", kind),
+                    }
+                }
+            },
+            ["role", role] if self.definition(role).is_some() => {
+                if let Some(definition) = self.definition(role) {
+                    println!("{}: {}", definition.name, definition.description);
+                }
+            },
+            ["role", role] => {
+                let catalogue = self.game.catalogue();
+                let role = catalogue.find_role(role).ok_or_else(|| format!("unknown role '{}'", role))?;
+                println!("{}: {}", catalogue.role_name(&role), catalogue.role_description(&role));
+            },
+            ["add", name, role, rest @ ..] => self.add(name, role, rest)?,
+            ["save", file] => self.game.save(file).map_err(|e| e.to_string())?,
+            ["load", file] => {
+                self.game = Game::load(file).map_err(|e| e.to_string())?;
+                self.votes.clear();
+            },
+            ["roles", file] => {
+                self.definitions = load_definitions(file).map_err(|e| e.to_string())?;
+                let names: Vec<&str> = self.definitions.iter().map(|definition| definition.name.as_str()).collect();
+                println!("loaded {}", names.join(", "));
+            },
+            ["catalogue", file] => self.game.set_catalogue(Catalogue::load(file).map_err(|e| e.to_string())?),
+            ["act", player, action] => self.act(player, action, None)?,
+            ["act", player, action, target] => self.act(player, action, Some(target))?,
+            ["preview"] => self.preview(),
+            ["resolve"] => self.resolve()?,
+            ["results"] => self.results(None)?,
+            ["results", player] => self.results(Some(player))?,
+            ["messages"] => self.messages(None)?,
+            ["messages", player] => self.messages(Some(player))?,
+            ["write", dir] => self.write_messages(dir)?,
+            ["graph", format] => println!("{}", self.graph(format)?),
+            ["graph", format, file] => fs::write(file, self.graph(format)?).map_err(|e| e.to_string())?,
+            ["vote", voter, target] => self.vote(voter, target)?,
+            ["unvote", voter] => {
+                let voter = self.find(voter)?;
+                self.votes.remove(&voter);
+            },
+            ["tally"] => self.tally(),
+            ["lynch"] => self.lynch(None)?,
+            ["lynch", player] => self.lynch(Some(player))?,
+            ["nolynch"] => self.end_day()?,
+            ["confirm", player] => {
+                let player = self.find(player)?;
+                self.game.submit_action(player, ActionKind::Confirm, None).map_err(|e| self.refusal(e))?;
+                println!("{} is confirmed as town", self.name(player));
+            },
+            ["shoot", player, target] => {
+                let player = self.find(player)?;
+                let target = self.find(target)?;
+                let deaths = self.game.state().graveyard.len();
+                self.game.submit_action(player, ActionKind::Shoot, Some(target)).map_err(|e| self.refusal(e))?;
+                self.votes.remove(&target);
+                self.votes.retain(|_, voted| *voted != target);
+                self.announce(deaths);
+            },
+            _ => return Err(String::from("unknown command, type 'help' for commands")),
+        }
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Result<PlayerId, String> {
+        self.game.player(name).ok_or_else(|| format!("no player named '{}'", name))
+    }
+
+    fn name(&self, player: PlayerId) -> String {
+        self.game.name_of(player).unwrap_or_else(|| String::from("?"))
+    }
+
+    /// Why the game refused a command, in the game's language
+    fn refusal(&self, error: ActionError) -> String {
+        self.game.catalogue().action_error(&error)
+    }
+
+    fn living(&self) -> Vec<PlayerId> {
+        self.game.state().players.iter()
+            .filter(|player| player.alive)
+            .map(|player| player.id)
+            .collect()
+    }
+
+    fn list_players(&self) {
+        let catalogue = self.game.catalogue();
+        for player in self.game.state().players.iter() {
+            let role = self.game.role_of(player.id).map(|role| catalogue.role_name(&role)).unwrap_or_default();
+            let faction = self.game.faction_of(player.id).map(|faction| catalogue.faction(&faction)).unwrap_or_default();
+            let status = if player.alive { "alive" } else { "dead" };
+            println!("  {:<12} {:<10} {:<16} {}", player.name, faction, role, status);
+        }
+    }
+
+    /// A loaded role definition with the name
+    fn definition(&self, name: &str) -> Option<&RoleDefinition> {
+        self.definitions.iter().find(|definition| definition.is_named(name))
+    }
+
+    fn add(&mut self, name: &str, role: &str, rest: &[&str]) -> Result<(), String> {
+        if self.game.player(name).is_some() {
+            return Err(format!("there is already a player named '{}'", name));
+        }
+        let (faction, modifiers) = match rest.split_first() {
+            Some((faction, modifiers)) if faction.parse::<Faction>().is_ok() => (faction.parse().ok(), modifiers),
+            _ => (None, rest),
+        };
+        let faction = faction.unwrap_or(Faction::Town);
+        let modifiers = modifiers.iter()
+            .map(|modifier| modifier.parse())
+            .collect::<Result<Vec<Modifier>, _>>()
+            .map_err(|e| e.to_string())?;
+        let player = match self.definition(role).cloned() {
+            Some(definition) => self.game.add_defined_player(name, faction, &definition, modifiers),
+            None => {
+                let role = self.game.catalogue().find_role(role).ok_or_else(|| format!("unknown role '{}'", role))?;
+                self.game.add_player(name, faction, role, modifiers)
+            },
+        };
+        let catalogue = self.game.catalogue();
+        if let (Some(role), Some(faction)) = (self.game.role_of(player), self.game.faction_of(player)) {
+            println!("added {} as {} {}", name, catalogue.faction(&faction), catalogue.role_name(&role));
+        }
+        Ok(())
+    }
+
+    fn act(&mut self, player: &str, action: &str, target: Option<&str>) -> Result<(), String> {
+        let player = self.find(player)?;
+        let kind: ActionKind = action.parse().map_err(|e: ParseNameError| e.to_string())?;
+        let target = target.map(|target| self.find(target)).transpose()?;
+        self.game.submit_action(player, kind, target).map_err(|e| self.refusal(e))
+    }
+
+    /// Every submitted action, followed by the interactions the moderator should expect
+    fn preview(&self) {
+        let living = self.living();
+        let submitted: Vec<(PlayerId, ActionKind, Option<PlayerId>)> = living.iter()
+            .filter_map(|&player| self.game.submitted_action(player).map(|(kind, target)| (player, kind, target)))
+            .collect();
+        for (player, kind, target) in submitted.iter() {
+            let target = target.map(|target| self.name(target)).unwrap_or_else(|| String::from("nobody"));
+            println!("  {} {:?} {}", self.name(*player), kind, target);
+        }
+
+        let targets_of = |kind: ActionKind| submitted.iter()
+            .filter(move |(_, other, _)| *other == kind)
+            .filter_map(|(player, _, target)| target.map(|target| (*player, target)));
+
+        for &player in living.iter().filter(|_| self.game.phase() == Phase::Night) {
+            let night_actions = self.game.actions_of(player).iter()
+                .any(|kind| !kind.day_action() && self.game.charges_left(player, *kind) != Some(0));
+            if night_actions && self.game.submitted_action(player).is_none() {
+                println!("  ! {} has not submitted an action", self.name(player));
+            }
+        }
+        for (blocker, blocked) in targets_of(ActionKind::Block) {
+            if submitted.iter().any(|(player, _, _)| *player == blocked) {
+                println!("  ! {} blocks {}, whose action may fail", self.name(blocker), self.name(blocked));
+            }
+        }
+        for (doctor, saved) in targets_of(ActionKind::Save) {
+            for (killer, _) in targets_of(ActionKind::Kill).filter(|(_, target)| *target == saved) {
+                println!("  ! {} saves {} from {}", self.name(doctor), self.name(saved), self.name(killer));
+            }
+        }
+        for (framer, framed) in targets_of(ActionKind::Frame) {
+            for (cop, _) in targets_of(ActionKind::Cop).filter(|(_, target)| *target == framed) {
+                println!("  ! {} frames {}, who is checked by {}", self.name(framer), self.name(framed), self.name(cop));
+            }
+        }
+        let mut kills: HashMap<Faction, Vec<PlayerId>> = HashMap::new();
+        for (killer, _) in targets_of(ActionKind::Kill) {
+            if let Some(faction) = self.game.faction_of(killer).filter(Faction::shares_kill) {
+                kills.entry(faction).or_default().push(killer);
+            }
+        }
+        for (faction, killers) in kills.iter().filter(|(_, killers)| killers.len() > 1) {
+            let names: Vec<String> = killers.iter().map(|&killer| self.name(killer)).collect();
+            println!("  ! {} all kill for {}, only one kill is made", names.join(", "), faction);
+        }
+    }
+
+    fn resolve(&mut self) -> Result<(), String> {
+        if self.game.phase() != Phase::Night {
+            return Err(String::from("it is not night"));
+        }
+        let deaths = self.game.state().graveyard.len();
+        self.game.advance_phase();
+        self.results(None)?;
+        self.announce(deaths);
+        Ok(())
+    }
+
+    fn results(&self, player: Option<&str>) -> Result<(), String> {
+        let players = match player {
+            Some(player) => vec![self.find(player)?],
+            None => self.game.state().players.iter().map(|player| player.id).collect(),
+        };
+        for player in players {
+            if let Some(result) = self.game.results_for(player) {
+                let outcome = if result.success { "succeeded" } else { "failed" };
+                // the player is never told their sanity, but the moderator needs it to read the result
+                match self.game.sanity_of(player) {
+                    Some(sanity) => println!("  {}: {} ({}, {:?})", self.name(player), result.val, outcome, sanity),
+                    None         => println!("  {}: {} ({})", self.name(player), result.val, outcome),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn messages(&self, player: Option<&str>) -> Result<(), String> {
+        let players = match player {
+            Some(player) => vec![self.find(player)?],
+            None => self.game.state().players.iter().map(|player| player.id).collect(),
+        };
+        for player in players {
+            for message in self.game.messages_for(player) {
+                println!("To {}:\n{}\n", self.name(player), message);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_messages(&self, dir: &str) -> Result<(), String> {
+        let last = self.game.last_night().ok_or_else(|| String::from("no night has been resolved yet"))?;
+        let written = self.game.write_messages(dir, &last).map_err(|e| e.to_string())?;
+        for path in written {
+            println!("  wrote {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// The last night's visit graph as Graphviz or JSON
+    fn graph(&self, format: &str) -> Result<String, String> {
+        if self.game.last_night().is_none() {
+            return Err(String::from("no night has been resolved yet"));
+        }
+        let export = self.game.visit_export();
+        match format {
+            "dot"  => Ok(export.to_dot()),
+            "json" => export.to_json().map_err(|e| e.to_string()),
+            _      => Err(format!("unknown graph format '{}', use dot or json", format)),
+        }
+    }
+
+    /// Print deaths after the first `known` graves, and the winners if the game has ended
+    fn announce(&self, known: usize) {
+        let catalogue = self.game.catalogue();
+        let state = self.game.state();
+        for grave in state.graveyard.iter().skip(known) {
+            println!("{}", catalogue.render("grave", &[
+                ("player", &grave.name),
+                ("flip", &catalogue.flip(&grave.flip)),
+                ("time", &catalogue.time(&grave.phase, &grave.night)),
+                ("cause", &catalogue.render(&cause_key(&grave.cause), &[])),
+            ]));
+        }
+        if state.game_over {
+            println!("{}", catalogue.render("game_over", &[]));
+            for &winner in state.winners.iter() {
+                if let Some(faction) = self.game.faction_of(winner) {
+                    println!("{}", catalogue.render("winner", &[("player", &self.name(winner)), ("faction", &catalogue.faction(&faction))]));
+                }
+            }
+        }
+    }
+
+    fn vote(&mut self, voter: &str, target: &str) -> Result<(), String> {
+        if self.game.phase() != Phase::Day {
+            return Err(String::from("it is not day"));
+        }
+        let living = self.living();
+        let voter = self.find(voter)?;
+        let target = self.find(target)?;
+        if !living.contains(&voter) || !living.contains(&target) {
+            return Err(String::from("only living players can vote or be voted for"));
+        }
+        self.votes.insert(voter, target);
+        Ok(())
+    }
+
+    /// Living players' votes per target, most votes first
+    fn count_votes(&self) -> Vec<(PlayerId, Vec<PlayerId>)> {
+        let living = self.living();
+        let mut count: Vec<(PlayerId, Vec<PlayerId>)> = Vec::new();
+        for (voter, target) in self.votes.iter().filter(|(voter, _)| living.contains(voter)) {
+            match count.iter_mut().find(|(other, _)| other == target) {
+                Some((_, voters)) => voters.push(*voter),
+                None => count.push((*target, vec![*voter])),
+            }
+        }
+        count.sort_by_key(|(_, voters)| std::cmp::Reverse(voters.len()));
+        count
+    }
+
+    fn tally(&self) {
+        let majority = self.living().len() / 2 + 1;
+        for (target, voters) in self.count_votes() {
+            let names: Vec<String> = voters.iter().map(|&voter| self.name(voter)).collect();
+            println!("  {} ({}/{}): {}", self.name(target), voters.len(), majority, names.join(", "));
+        }
+    }
+
+    fn lynch(&mut self, player: Option<&str>) -> Result<(), String> {
+        let player = match player {
+            Some(player) => self.find(player)?,
+            None => match self.count_votes().as_slice() {
+                [] => return Err(String::from("no votes have been cast")),
+                [(_, first), (_, second), ..] if first.len() == second.len() => {
+                    return Err(String::from("the vote is tied, name who to lynch"));
+                },
+                [(leader, _), ..] => *leader,
+            },
+        };
+        let deaths = self.game.state().graveyard.len();
+        self.game.lynch(player).map_err(|e| self.refusal(e))?;
+        self.announce(deaths);
+        self.end_day()
+    }
+
+    fn end_day(&mut self) -> Result<(), String> {
+        if self.game.phase() != Phase::Day {
+            return Err(String::from("it is not day"));
+        }
+        self.votes.clear();
+        self.game.advance_phase();
+        Ok(())
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use super::components::{DeathCause, Faction, Modifier, Role};
+use super::components::attributes::{Innocence, RoleKind};
+use super::resources::{Flip, Night, Phase};
+use super::components::actions::ActionKind;
+use super::validation::ActionError;
+
+/// Built-in English templates, as (key, template)
+const ENGLISH: &[(&str, &str)] = &[
+    ("action.cop.success",        "You checked {target}. They are {result}."),
+    ("action.cop.failure",        "You checked {target}, but learned nothing."),
+    ("action.detective.success",  "You investigated {target}. Their role is {result}."),
+    ("action.detective.failure",  "You investigated {target}, but learned nothing."),
+    ("action.track.success",      "You tracked {target}. They visited {result}."),
+    ("action.track.failure",      "You tracked {target}, but did not see where they went."),
+    ("action.watch.success",      "You watched {target}. They were visited by {result}."),
+    ("action.watch.failure",      "You watched {target}, but did not see who visited them."),
+    ("action.block.success",      "You roleblocked {target}."),
+    ("action.block.failure",      "You were unable to roleblock {target}."),
+    ("action.save.success",       "You protected {target}."),
+    ("action.save.failure",       "You were unable to protect {target}."),
+    ("action.kill.success",       "You killed {target}."),
+    ("action.kill.failure",       "Your attempt to kill {target} failed."),
+    ("action.befriend.success",   "You revealed your alignment to {target}."),
+    ("action.befriend.failure",   "You were unable to reach {target}."),
+    ("action.confirm.success",    "You revealed yourself as town."),
+    ("action.confirm.failure",    "You were unable to reveal yourself."),
+    ("action.frame.success",      "You framed {target}."),
+    ("action.frame.failure",      "You were unable to frame {target}."),
+    ("action.clean.success",      "You cleaned up after {target}. Their role was {result}."),
+    ("action.clean.failure",      "You were unable to clean up after {target}."),
+    ("action.gun_check.success",  "You inspected {target}'s belongings. They are {result}."),
+    ("action.gun_check.failure",  "You inspected {target}'s belongings, but learned nothing."),
+    ("action.give_gun.success",   "You gave {target} a gun."),
+    ("action.give_gun.failure",   "You were unable to give {target} a gun."),
+    ("action.shoot.success",      "You shot {target}."),
+    ("action.shoot.failure",      "You were unable to shoot {target}."),
+    ("action.modifier_cop.success", "You checked {target}. Their modifiers are {result}."),
+    ("action.modifier_cop.failure", "You checked {target}, but learned nothing."),
+    ("action.vanilla_cop.success", "You checked {target}. They are {result}."),
+    ("action.vanilla_cop.failure", "You checked {target}, but learned nothing."),
+    ("action.alignment_role_cop.success", "You checked {target}. They are {result}."),
+    ("action.alignment_role_cop.failure", "You checked {target}, but learned nothing."),
+    ("death.mafia_kill",          "You were killed by the mafia."),
+    ("death.vigilante",           "You were shot by a vigilante."),
+    ("death.serial_killer",       "You were killed by a serial killer."),
+    ("death.lynch",               "You were lynched."),
+    ("death.modkill",             "You were removed from the game by the moderator."),
+    ("death.bomb",                "You were caught in the blast of the bomb you killed."),
+    ("death.poison",              "You succumbed to poison."),
+    ("death.shot",                "You were shot during the day."),
+    ("cause.mafia_kill",          "mafia kill"),
+    ("cause.vigilante",           "vigilante"),
+    ("cause.serial_killer",       "serial killer"),
+    ("cause.lynch",               "lynch"),
+    ("cause.modkill",             "modkill"),
+    ("cause.bomb",                "bomb"),
+    ("cause.poison",              "poison"),
+    ("cause.shot",                "gunshot"),
+    ("reveal.faction",            "{target} revealed to you that they are {result}."),
+    ("received.gun",              "You were given a gun. You may shoot a player once during the day."),
+    ("gun.armed",                 "armed"),
+    ("gun.unarmed",               "unarmed"),
+    ("results.header",            "Night {night} results:"),
+    ("results.line",              "{modifiers}{role} {player} targets {target} - {outcome} - {result}"),
+    ("results.died",              "{role} {player} died ({cause})"),
+    ("grave",                     "{player} ({flip}) died {time} by {cause}"),
+    ("time.day",                  "day {number}"),
+    ("time.night",                "night {number}"),
+    ("winner",                    "{player} ({faction}) wins"),
+    ("game_over",                 "Game over"),
+    ("role.vanilla",              "Vanilla"),
+    ("role.vanilla.description", "You have no night action. Find and lynch the scum."),
+    ("role.cop",                  "Cop"),
+    ("role.cop.description", "Each night, you may check a player to learn whether they are innocent or guilty."),
+    ("role.sheriff",              "Sheriff"),
+    ("role.sheriff.description", "Each night, you may check a player to learn whether they are innocent or guilty."),
+    ("role.detective",            "Detective"),
+    ("role.detective.description", "Each night, you may investigate a player to learn their role."),
+    ("role.tracker",              "Tracker"),
+    ("role.tracker.description", "Each night, you may track a player to learn who they visit."),
+    ("role.watcher",              "Watcher"),
+    ("role.watcher.description", "Each night, you may watch a player to learn who visits them."),
+    ("role.roleblocker",          "Roleblocker"),
+    ("role.roleblocker.description", "Each night, you may block a player, stopping their night action."),
+    ("role.doctor",               "Doctor"),
+    ("role.doctor.description", "Each night, you may protect a player from being killed."),
+    ("role.vigilante",            "Vigilante"),
+    ("role.vigilante.description", "Each night, you may shoot a player."),
+    ("role.bomb",                 "Bomb"),
+    ("role.bomb.description", "Anyone who kills you at night dies with you."),
+    ("role.goon",                 "Goon"),
+    ("role.goon.description", "You know your teammates, and may make your team's night kill."),
+    ("role.godfather",            "Godfather"),
+    ("role.godfather.description", "You appear innocent to cops, and may make your team's night kill."),
+    ("role.framer",               "Framer"),
+    ("role.framer.description", "Each night, you may frame a player so they appear guilty to cops."),
+    ("role.janitor",              "Janitor"),
+    ("role.janitor.description", "Each night, you may clean up after a player who dies that night, hiding their role and learning it yourself."),
+    ("role.mason",                "Mason"),
+    ("role.mason.description", "You know the other masons, and they know you are town."),
+    ("role.neighbor",             "Neighbor"),
+    ("role.neighbor.description", "You know the other neighbors, who may talk privately with you."),
+    ("role.friendly_neighbor",    "Friendly Neighbor"),
+    ("role.friendly_neighbor.description", "Each night, you may reveal your alignment to a player."),
+    ("role.innocent_child",       "Innocent Child"),
+    ("role.innocent_child.description", "Once during the day, you may reveal yourself as town to everyone."),
+    ("role.serial_killer",        "Serial Killer"),
+    ("role.serial_killer.description", "Each night, you may kill a player. You win if you are among the last two players alive."),
+    ("role.jester",               "Jester"),
+    ("role.jester.description", "You win if you are lynched."),
+    ("role.survivor",             "Survivor"),
+    ("role.survivor.description", "You win if you are alive at the end of the game."),
+    ("role.jack_of_all_trades",   "Jack-of-all-Trades"),
+    ("role.jack_of_all_trades.description", "You may check, protect or shoot a player, each once per game, using one ability a night."),
+    ("role.gunsmith",             "Gunsmith"),
+    ("role.gunsmith.description", "Each night, you may inspect a player to learn whether they have a gun."),
+    ("role.gun_dealer",           "Gun Dealer"),
+    ("role.gun_dealer.description", "Each night, you may give a player a gun, which they may shoot once during the day."),
+    ("role.gun_owner",            "Gun Owner"),
+    ("role.gun_owner.description", "Once during the day, you may shoot a player."),
+    ("role.role_cop",             "Role Cop"),
+    ("role.role_cop.description", "Each night, you may check a player to learn their exact role."),
+    ("role.modifier_cop",         "Modifier Cop"),
+    ("role.modifier_cop.description", "Each night, you may check a player to learn their modifiers."),
+    ("role.vanilla_cop",          "Vanilla Cop"),
+    ("role.vanilla_cop.description", "Each night, you may check a player to learn whether they are vanilla or have a power role."),
+    ("role.alignment_role_cop",   "Alignment-Role Cop"),
+    ("role.alignment_role_cop.description", "Each night, you may check a player to learn both their alignment and their role."),
+    ("faction.town",              "Town"),
+    ("faction.mafia",             "Mafia"),
+    ("faction.mafia_team",        "Mafia {team}"),
+    ("faction.serial_killer",     "Serial Killer"),
+    ("faction.jester",            "Jester"),
+    ("faction.survivor",          "Survivor"),
+    ("innocence.innocent",        "Innocent"),
+    ("innocence.guilty",          "Guilty"),
+    ("role_kind.vanilla",         "vanilla"),
+    ("role_kind.power",           "a power role"),
+    ("modifier.breakthrough",     "Breakthrough"),
+    ("modifier.macho",            "Macho"),
+    ("modifier.naive",            "Naive"),
+    ("modifier.paranoid",         "Paranoid"),
+    ("modifier.insane",           "Insane"),
+    ("modifier.random",           "Random"),
+    ("modifier.astral",           "Astral"),
+    ("modifiers.none",            "none"),
+    ("report.alignment_role",     "{alignment} {role}"),
+    ("flip.role",                 "{faction} {role}"),
+    ("flip.cleaned",              "cleaned"),
+    ("flip.unrevealed",           "unrevealed"),
+    ("word.nobody",               "nobody"),
+    ("word.success",              "success"),
+    ("word.failure",              "fail"),
+    ("error.dead_target",         "target is dead"),
+    ("error.self_target",         "action cannot target yourself"),
+    ("error.no_charges",          "no uses of the action left"),
+    ("error.wrong_phase",         "action cannot be used now"),
+    ("error.consecutive_target",  "target was chosen last night"),
+    ("error.unknown_player",      "no such player"),
+    ("error.dead_player",         "dead players cannot act"),
+    ("error.missing_action",      "player does not have that action"),
+    ("error.already_lynched",     "someone has already been lynched today"),
+];
+
+/// Convert a `Debug` variant name such as `MafiaKill` to the `mafia_kill` form used in keys
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+/// Key of the message telling a player how their action went
+pub fn action_key(kind: ActionKind, success: bool) -> String {
+    format!("action.{}.{}", snake_case(&format!("{:?}", kind)), if success { "success" } else { "failure" })
+}
+
+/// Key of the message telling a player how they died
+pub fn death_key(cause: &DeathCause) -> String {
+    format!("death.{}", snake_case(&format!("{:?}", cause)))
+}
+
+/// Key of the name a role is shown as
+/// Custom roles have their own namespace, so that a definition called e.g. "Cop" does not take over the built-in role's text
+pub fn role_key(role: &Role) -> String {
+    match role {
+        Role::Custom(name) => format!("role.custom.{}", name.to_lowercase().replace(' ', "_")),
+        _                  => format!("role.{}", snake_case(&format!("{:?}", role))),
+    }
+}
+
+/// Key of the description given to a player with the role
+pub fn role_description_key(role: &Role) -> String {
+    format!("{}.description", role_key(role))
+}
+
+/// Key of the short name of a cause of death
+pub fn cause_key(cause: &DeathCause) -> String {
+    format!("cause.{}", snake_case(&format!("{:?}", cause)))
+}
+
+/// Key of the reason a submitted action was rejected
+pub fn error_key(error: &ActionError) -> String {
+    format!("error.{}", snake_case(&format!("{:?}", error)))
+}
+
+/// The built-in English template for the key, for text shown outside of a game, or the key itself if there is none
+pub fn english(key: &str) -> &str {
+    ENGLISH.iter().find(|(other, _)| *other == key).map_or(key, |(_, template)| template)
+}
+
+/// Every template used for game output in one language, keyed by event or result type
+/// Templates name their values in braces, e.g. `{target}`; unknown keys fall back to English
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Catalogue {
+    pub language: String,
+    templates: HashMap<String, String>,
+}
+
+impl Default for Catalogue {
+    fn default() -> Self {
+        Catalogue::english()
+    }
+}
+
+impl Catalogue {
+    pub fn english() -> Catalogue {
+        Catalogue {
+            language: String::from("en"),
+            templates: ENGLISH.iter().map(|(key, template)| (key.to_string(), template.to_string())).collect(),
+        }
+    }
+
+    /// Read a catalogue from JSON, e.g. a translation or a themed setup's overrides
+    /// Keys it does not define keep their English template
+    pub fn from_json(json: &str) -> serde_json::Result<Catalogue> {
+        let mut catalogue = Catalogue::english();
+        catalogue.merge(serde_json::from_str(json)?);
+        Ok(catalogue)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Catalogue> {
+        Catalogue::from_json(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Replace the template for a key
+    pub fn set(&mut self, key: &str, template: &str) {
+        self.templates.insert(key.to_string(), template.to_string());
+    }
+
+    /// Take the language and every template of `other`, keeping templates it does not define
+    pub fn merge(&mut self, other: Catalogue) {
+        self.language = other.language;
+        self.templates.extend(other.templates);
+    }
+
+    /// The template for the key, or the key itself if there is none
+    pub fn template<'a>(&'a self, key: &'a str) -> &'a str {
+        self.templates.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    /// Rename a role for a themed setup, e.g. calling the `Cop` an "Inspector"
+    /// The role keeps its mechanics, only how it is shown changes
+    pub fn theme_role(&mut self, role: &Role, name: &str, description: &str) {
+        self.set(&role_key(role), name);
+        self.set(&role_description_key(role), description);
+    }
+
+    /// Custom roles are called by their defined name unless the catalogue renames them
+    pub fn role_name(&self, role: &Role) -> String {
+        match (role, self.templates.get(&role_key(role))) {
+            (_, Some(name))            => name.clone(),
+            (Role::Custom(name), None) => name.clone(),
+            (_, None)                  => role_key(role),
+        }
+    }
+
+    pub fn role_description(&self, role: &Role) -> String {
+        self.render(&role_description_key(role), &[])
+    }
+
+    /// The role shown by the name, checking themed names before the mechanical names
+    /// Case and spaces are ignored
+    pub fn find_role(&self, name: &str) -> Option<Role> {
+        let name = name.replace(' ', "");
+        Role::ALL.iter()
+            .find(|role| self.role_name(role).replace(' ', "").eq_ignore_ascii_case(&name))
+            .cloned()
+            .or_else(|| name.parse().ok())
+    }
+
+    pub fn innocence(&self, innocence: &Innocence) -> String {
+        self.render(&format!("innocence.{}", snake_case(&format!("{:?}", innocence))), &[])
+    }
+
+    pub fn role_kind(&self, kind: &RoleKind) -> String {
+        self.render(&format!("role_kind.{}", snake_case(&format!("{:?}", kind))), &[])
+    }
+
+    /// A list of modifiers as shown to a modifier cop
+    pub fn modifiers(&self, modifiers: &[Modifier]) -> String {
+        if modifiers.is_empty() {
+            return self.render("modifiers.none", &[]);
+        }
+        modifiers.iter()
+            .map(|modifier| self.render(&format!("modifier.{}", snake_case(&format!("{:?}", modifier))), &[]))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// A day or night, e.g. when a player died
+    /// A day shares its number with the night to come
+    pub fn time(&self, phase: &Phase, night: &Night) -> String {
+        let key = match phase {
+            Phase::Day   => "time.day",
+            Phase::Night => "time.night",
+        };
+        self.render(key, &[("number", &night.0.to_string())])
+    }
+
+    /// Mafia teams after the first are numbered, e.g. "Mafia 2"
+    pub fn faction(&self, faction: &Faction) -> String {
+        match faction {
+            Faction::Mafia(0)    => self.render("faction.mafia", &[]),
+            Faction::Mafia(team) => self.render("faction.mafia_team", &[("team", &team.to_string())]),
+            _                    => self.render(&format!("faction.{}", snake_case(&format!("{:?}", faction))), &[]),
+        }
+    }
+
+    pub fn action_error(&self, error: &ActionError) -> String {
+        self.render(&error_key(error), &[])
+    }
+
+    /// What a dead player was revealed as, with themed role names
+    pub fn flip(&self, flip: &Flip) -> String {
+        match flip {
+            Flip::Role(role, faction) => self.render("flip.role", &[
+                ("faction", &self.faction(faction)),
+                ("role", &self.role_name(role)),
+            ]),
+            Flip::Alignment(faction)  => self.faction(faction),
+            Flip::Unrevealed          => self.render("flip.unrevealed", &[]),
+            Flip::Cleaned             => self.render("flip.cleaned", &[]),
+        }
+    }
+
+    /// Fill in the key's template with the named values
+    pub fn render(&self, key: &str, values: &[(&str, &str)]) -> String {
+        values.iter().fold(self.template(key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factions_and_errors_come_from_the_catalogue() {
+        let mut catalogue = Catalogue::english();
+        assert_eq!(catalogue.faction(&Faction::Mafia(0)), "Mafia");
+        assert_eq!(catalogue.faction(&Faction::Mafia(2)), "Mafia 2");
+        assert_eq!(catalogue.faction(&Faction::SerialKiller), "Serial Killer");
+        assert_eq!(catalogue.action_error(&ActionError::DeadTarget), ActionError::DeadTarget.to_string());
+
+        catalogue.set("faction.town", "Village");
+        catalogue.set("error.dead_target", "cible morte");
+        assert_eq!(catalogue.flip(&Flip::Alignment(Faction::Town)), "Village");
+        assert_eq!(catalogue.flip(&Flip::Role(Role::Cop, Faction::Town)), "Village Cop");
+        assert_eq!(catalogue.action_error(&ActionError::DeadTarget), "cible morte");
+    }
+}
@@ -1,27 +1,168 @@
 use std::fmt;
 use std::fmt::Display;
+use std::str::FromStr;
 use derive_display_from_debug::Display;
 use specs::{Component, Entity, VecStorage, NullStorage};
-use super::resources::Night;
+use serde::{Serialize, Deserialize};
+use super::resources::{Night, Phase};
+use actions::ActionKind;
 pub mod actions;
 pub mod attributes;
+pub mod knowledge;
 
 /// The name of the player as other players can identify them
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
 pub struct Name(pub String);
 
-#[derive(Component)]
+/// A name typed by a user that does not match any variant
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseNameError(pub String);
+
+impl fmt::Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown name '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseNameError {}
+
+/// Find the variant whose `Debug` name matches, ignoring case and spaces
+pub(crate) fn parse_name<T: Clone + fmt::Debug>(variants: &[T], s: &str) -> Result<T, ParseNameError> {
+    let name = s.replace(' ', "");
+    variants.iter()
+        .find(|variant| format!("{:?}", variant).eq_ignore_ascii_case(&name))
+        .cloned()
+        .ok_or_else(|| ParseNameError(s.to_string()))
+}
+
+/// Inner value of `Mafia` distinguishes independent mafia teams in the same game
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[storage(VecStorage)]
 pub enum Faction {
     Town,
-    Mafia,
+    Mafia(u8),
+    SerialKiller,
+    Jester,
+    Survivor,
+}
+
+impl Faction {
+    /// Whether the faction has a night kill, and so must be eliminated before its opponents can win
+    pub fn kill_capable(&self) -> bool {
+        matches!(self, Faction::Mafia(_) | Faction::SerialKiller)
+    }
+
+    /// Whether members of the faction know who each other are
+    pub fn informed(&self) -> bool {
+        matches!(self, Faction::Mafia(_))
+    }
+
+    /// Whether the faction has a single night kill shared between its members
+    pub fn shares_kill(&self) -> bool {
+        matches!(self, Faction::Mafia(_))
+    }
+}
+
+impl fmt::Display for Faction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Faction::Mafia(0)    => write!(f, "Mafia"),
+            Faction::Mafia(team) => write!(f, "Mafia {}", team),
+            _                    => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// Parses the `Display` form, e.g. "Town" or "Mafia 2", ignoring case and spaces
+impl FromStr for Faction {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.replace(' ', "").to_ascii_lowercase();
+        match name.as_str() {
+            "town"         => Ok(Faction::Town),
+            "mafia"        => Ok(Faction::Mafia(0)),
+            "serialkiller" => Ok(Faction::SerialKiller),
+            "jester"       => Ok(Faction::Jester),
+            "survivor"     => Ok(Faction::Survivor),
+            _ => name.strip_prefix("mafia")
+                .and_then(|team| team.parse().ok())
+                .map(Faction::Mafia)
+                .ok_or_else(|| ParseNameError(s.to_string())),
+        }
+    }
 }
 
-/// Inner is the night died
-#[derive(Component)]
+/// How a player died
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DeathCause {
+    MafiaKill,
+    Vigilante,
+    SerialKiller,
+    Lynch,
+    Modkill,
+    Bomb,
+    Poison,
+    Shot,
+}
+
+impl fmt::Display for DeathCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cause = match self {
+            DeathCause::MafiaKill    => "mafia kill",
+            DeathCause::Vigilante    => "vigilante",
+            DeathCause::SerialKiller => "serial killer",
+            DeathCause::Lynch        => "lynch",
+            DeathCause::Modkill      => "modkill",
+            DeathCause::Bomb         => "bomb",
+            DeathCause::Poison       => "poison",
+            DeathCause::Shot         => "gunshot",
+        };
+        write!(f, "{}", cause)
+    }
+}
+
+impl DeathCause {
+    /// The cause of death for a night kill made by a member of the faction
+    pub fn of_kill_by(faction: &Faction) -> DeathCause {
+        match faction {
+            Faction::Mafia(_)     => DeathCause::MafiaKill,
+            Faction::SerialKiller => DeathCause::SerialKiller,
+            _                     => DeathCause::Vigilante,
+        }
+    }
+}
+
+/// When the player died, how, and who by if known
+/// A death by day is on the night to come, as with `CurrentNight`
+#[derive(Component, Clone, Debug)]
 #[storage(VecStorage)]
-pub struct Dead(pub Night);
+pub struct Dead {
+    pub night: Night,
+    pub phase: Phase,
+    pub cause: DeathCause,
+    pub killer: Option<Entity>,
+}
+
+/// A private group whose members are told of each other at the start of the game
+#[derive(Clone, Debug, Display, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Group {
+    Masons,
+    Neighbors,
+}
+
+impl Group {
+    /// Whether being in the group also confirms a member's faction to the others
+    pub fn reveals_faction(&self) -> bool {
+        matches!(self, Group::Masons)
+    }
+}
+
+/// Private groups the player belongs to
+#[derive(Component, Clone, Debug, Default)]
+#[storage(VecStorage)]
+pub struct Groups(pub Vec<Group>);
 
 /// Signifies that player is dead and did not die tonight
 #[derive(Component, Default)]
@@ -34,30 +175,100 @@ pub struct LongDead;
 #[storage(VecStorage)]
 pub struct Target(pub Option<Entity>);
 
-/// Current location, relative to another player, based on visiting night action
-/// None if player 'went nowhere' on current night
-#[derive(Component, Debug)]
-#[storage(VecStorage)]
-pub struct Position(pub Option<Entity>);
+/// A problem with the world that stopped an action resolving
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResolveError<P = Entity> {
+    /// Entity is missing a component needed to resolve the action, by component type name
+    MissingComponent(P, String),
+}
+
+impl<P: Copy> ResolveError<P> {
+    /// The same error with the player referred to another way
+    pub fn map_players<Q>(&self, f: impl Fn(P) -> Q) -> ResolveError<Q> {
+        match self {
+            ResolveError::MissingComponent(ent, component) => ResolveError::MissingComponent(f(*ent), component.clone()),
+        }
+    }
+}
 
-impl fmt::Display for Position {
+impl<P: fmt::Debug> fmt::Display for ResolveError<P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(ent) = self.0 {
-            write!(f, "{:?}", ent)
-        } else {
-            write!(f, "nowhere")
+        match self {
+            ResolveError::MissingComponent(ent, component) => write!(f, "{:?} has no {}", ent, component),
         }
     }
 }
 
-#[derive(Component, Debug)]
-#[storage(VecStorage)]
-pub struct NightResult {
+/// `error` is set when the action failed because of a problem with the world rather than the game
+#[derive(Clone, Debug)]
+pub struct NightResult<P = Entity> {
+    /// The ability the result is for
+    pub action: ActionKind,
     pub success: bool,
     pub val: String,
+    /// What the player learned, for clients to present however they like
+    pub facts: Vec<knowledge::Fact<P>>,
+    pub error: Option<ResolveError<P>>,
 }
 
-#[derive(Component, Clone, Debug, Display)]
+impl Component for NightResult {
+    type Storage = VecStorage<Self>;
+}
+
+impl<P: Copy> NightResult<P> {
+    /// The same result with each player referred to another way
+    pub fn map_players<Q>(&self, f: impl Fn(P) -> Q) -> NightResult<Q> {
+        NightResult {
+            action: self.action,
+            success: self.success,
+            val: self.val.clone(),
+            facts: self.facts.iter().map(|fact| fact.map_players(&f)).collect(),
+            error: self.error.as_ref().map(|error| error.map_players(&f)),
+        }
+    }
+}
+
+/// Uses left of each limited ability, as (ability, uses)
+/// Abilities not listed may be used every night
+#[derive(Component, Clone, Debug, Default)]
+#[storage(VecStorage)]
+pub struct Charges(pub Vec<(ActionKind, usize)>);
+
+impl Charges {
+    /// Uses left of the ability, or None if it is not limited
+    pub fn left(&self, kind: ActionKind) -> Option<usize> {
+        self.0.iter().find(|(other, _)| *other == kind).map(|(_, uses)| *uses)
+    }
+
+    /// Use up one use of the ability, if it is limited
+    pub fn spend(&mut self, kind: ActionKind) {
+        if let Some((_, uses)) = self.0.iter_mut().find(|(other, _)| *other == kind) {
+            *uses = uses.saturating_sub(1);
+        }
+    }
+}
+
+/// Every save a doctor has made, as (night, target)
+#[derive(Component, Clone, Debug, Default)]
+#[storage(VecStorage)]
+pub struct SaveHistory(pub Vec<(Night, Entity)>);
+
+impl SaveHistory {
+    /// Target saved on the night before `night`, if any
+    pub fn previous_target(&self, night: &Night) -> Option<Entity> {
+        self.0.iter()
+            .find(|(saved_night, _)| saved_night.0 + 1 == night.0)
+            .map(|(_, target)| *target)
+    }
+
+    /// Number of times the doctor has saved themself
+    pub fn self_saves(&self, doctor: Entity) -> usize {
+        self.0.iter().filter(|(_, target)| *target == doctor).count()
+    }
+}
+
+/// `Custom` roles are defined by a `RoleDefinition` rather than built in
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[storage(VecStorage)]
 pub enum Role {
     Vanilla,
@@ -68,14 +279,140 @@ pub enum Role {
     Watcher,
     Roleblocker,
     Doctor,
+    Vigilante,
+    Bomb,
     Goon,
     Godfather,
+    Framer,
+    Janitor,
+    Mason,
+    Neighbor,
+    FriendlyNeighbor,
+    InnocentChild,
+    SerialKiller,
+    Jester,
+    Survivor,
+    JackOfAllTrades,
+    Gunsmith,
+    GunDealer,
+    GunOwner,
+    RoleCop,
+    ModifierCop,
+    VanillaCop,
+    AlignmentRoleCop,
+    Custom(String),
 }
 
-#[derive(Clone, Debug, Display)]
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Custom(name) => write!(f, "{}", name),
+            _                  => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl Role {
+    pub const ALL: [Role; 29] = [
+        Role::Vanilla,
+        Role::Cop,
+        Role::Sheriff,
+        Role::Detective,
+        Role::Tracker,
+        Role::Watcher,
+        Role::Roleblocker,
+        Role::Doctor,
+        Role::Vigilante,
+        Role::Bomb,
+        Role::Goon,
+        Role::Godfather,
+        Role::Framer,
+        Role::Janitor,
+        Role::Mason,
+        Role::Neighbor,
+        Role::FriendlyNeighbor,
+        Role::InnocentChild,
+        Role::SerialKiller,
+        Role::Jester,
+        Role::Survivor,
+        Role::JackOfAllTrades,
+        Role::Gunsmith,
+        Role::GunDealer,
+        Role::GunOwner,
+        Role::RoleCop,
+        Role::ModifierCop,
+        Role::VanillaCop,
+        Role::AlignmentRoleCop,
+    ];
+
+    /// Whether the role has any power, as seen by a vanilla cop
+    /// Only an ability or passive of the role's own counts, not a faction kill, group chat or win condition
+    /// Custom roles get their kind from their `RoleDefinition`, so on their own they are vanilla
+    pub fn kind(&self) -> attributes::RoleKind {
+        match self {
+            Role::Vanilla | Role::Goon | Role::Mason | Role::Neighbor | Role::Jester | Role::Survivor | Role::Custom(_)
+                => attributes::RoleKind::Vanilla,
+            _   => attributes::RoleKind::Power,
+        }
+    }
+
+    /// Whether the role can only be given to a mafia team
+    pub fn mafia_only(&self) -> bool {
+        matches!(self, Role::Goon | Role::Godfather | Role::Framer | Role::Janitor)
+    }
+
+    /// The faction a player with this role ends up in when `requested` is asked for
+    /// Mafia roles keep a requested mafia team, but are never given to other factions
+    pub fn faction_for(&self, requested: Faction) -> Faction {
+        match (self, requested) {
+            (role, Faction::Mafia(team)) if role.mafia_only() => Faction::Mafia(team),
+            (role, _) if role.mafia_only() => Faction::Mafia(0),
+            (Role::Mason, _) | (Role::InnocentChild, _) | (Role::Bomb, _) => Faction::Town,
+            (Role::SerialKiller, _) => Faction::SerialKiller,
+            (Role::Jester, _)       => Faction::Jester,
+            (Role::Survivor, _)     => Faction::Survivor,
+            (_, requested)          => requested,
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(&Role::ALL, s)
+    }
+}
+
+#[derive(Clone, Debug, Display, PartialEq, Serialize, Deserialize)]
 pub enum Modifier {
     Breakthrough,
     Macho,
+    Naive,
+    Paranoid,
+    Insane,
+    Random,
+    Astral,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 7] = [
+        Modifier::Breakthrough,
+        Modifier::Macho,
+        Modifier::Naive,
+        Modifier::Paranoid,
+        Modifier::Insane,
+        Modifier::Random,
+        Modifier::Astral,
+    ];
+}
+
+impl FromStr for Modifier {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(&Modifier::ALL, s)
+    }
 }
 
 #[derive(Component, Clone, Debug, Display)]
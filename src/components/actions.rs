@@ -1,13 +1,110 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 use specs::{Component, VecStorage, NullStorage, Entity};
+use std::str::FromStr;
+use serde::{Serialize, Deserialize};
+use super::{parse_name, ParseNameError};
+
+/// The kinds of action a player can have, used when rules refer to actions by type
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionKind {
+    Cop,
+    Detective,
+    Track,
+    Watch,
+    Block,
+    Save,
+    Kill,
+    Befriend,
+    Confirm,
+    Frame,
+    Clean,
+    GunCheck,
+    GiveGun,
+    Shoot,
+    ModifierCop,
+    VanillaCop,
+    AlignmentRoleCop,
+}
+
+impl ActionKind {
+    pub const ALL: [ActionKind; 17] = [
+        ActionKind::Cop,
+        ActionKind::Detective,
+        ActionKind::Track,
+        ActionKind::Watch,
+        ActionKind::Block,
+        ActionKind::Save,
+        ActionKind::Kill,
+        ActionKind::Befriend,
+        ActionKind::Confirm,
+        ActionKind::Frame,
+        ActionKind::Clean,
+        ActionKind::GunCheck,
+        ActionKind::GiveGun,
+        ActionKind::Shoot,
+        ActionKind::ModifierCop,
+        ActionKind::VanillaCop,
+        ActionKind::AlignmentRoleCop,
+    ];
+
+    /// Whether the action is used during the day rather than at night
+    pub fn day_action(&self) -> bool {
+        matches!(self, ActionKind::Confirm | ActionKind::Shoot)
+    }
+
+    /// Whether the action takes a target at all
+    pub fn targeted(&self) -> bool {
+        !matches!(self, ActionKind::Confirm)
+    }
+
+    /// Whether using the action visits the target, so that trackers and watchers can see it
+    /// Day actions are made in public rather than by visiting
+    pub fn visits(&self) -> bool {
+        self.targeted() && !self.day_action()
+    }
+
+    /// Whether the player may use the action on themself
+    pub fn self_targetable(&self) -> bool {
+        matches!(self, ActionKind::Save)
+    }
+}
+
+impl FromStr for ActionKind {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(&ActionKind::ALL, s)
+    }
+}
 
 pub trait Action {
     fn new() -> Self;
+    fn kind(&self) -> ActionKind;
     fn active(&self) -> bool;
     fn target(&self) -> &Option<Entity>;
+    fn set(&mut self, active: bool, target: Option<Entity>);
 }
 
+/// Evaluate `$body` with `$A` naming the component type of the `ActionKind`
+/// This is the one table from kinds to components, e.g. `with_action!(kind, A => storage.insert(player, A::new()))`
+macro_rules! with_action {
+    ($kind:expr, $A:ident => $body:expr) => {
+        $crate::components::actions::with_action!(@each $kind, $A => $body;
+            Cop, Detective, Track, Watch, Block, Save, Kill, Befriend, Confirm, Frame, Clean,
+            GunCheck, GiveGun, Shoot, ModifierCop, VanillaCop, AlignmentRoleCop)
+    };
+    (@each $kind:expr, $A:ident => $body:expr; $($name:ident),*) => {
+        match $kind {
+            $($crate::components::actions::ActionKind::$name => {
+                type $A = $crate::components::actions::$name;
+                $body
+            })*
+        }
+    };
+}
+pub(crate) use with_action;
+
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
 pub struct Cop {
@@ -19,12 +116,19 @@ impl Action for Cop {
     fn new() -> Cop {
         Cop { active: false, target: None }
     }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Cop
+    }
     fn active(&self) -> bool {
         self.active
     }
     fn target(&self) -> &Option<Entity> {
         &self.target
     }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -38,12 +142,19 @@ impl Action for Detective {
     fn new() -> Detective {
         Detective { active: false, target: None }
     }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Detective
+    }
     fn active(&self) -> bool {
         self.active
     }
     fn target(&self) -> &Option<Entity> {
         &self.target
     }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -57,12 +168,19 @@ impl Action for Track {
     fn new() -> Track {
         Track { active: false, target: None }
     }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Track
+    }
     fn active(&self) -> bool {
         self.active
     }
     fn target(&self) -> &Option<Entity> {
         &self.target
     }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -76,12 +194,19 @@ impl Action for Watch {
     fn new() -> Watch {
         Watch { active: false, target: None }
     }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Watch
+    }
     fn active(&self) -> bool {
         self.active
     }
     fn target(&self) -> &Option<Entity> {
         &self.target
     }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -95,12 +220,19 @@ impl Action for Block {
     fn new() -> Block {
         Block { active: false, target: None }
     }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Block
+    }
     fn active(&self) -> bool {
         self.active
     }
     fn target(&self) -> &Option<Entity> {
         &self.target
     }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -114,12 +246,19 @@ impl Action for Save {
     fn new() -> Save {
         Save { active: false, target: None }
     }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Save
+    }
     fn active(&self) -> bool {
         self.active
     }
     fn target(&self) -> &Option<Entity> {
         &self.target
     }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -133,10 +272,277 @@ impl Action for Kill {
     fn new() -> Kill {
         Kill { active: false, target: None }
     }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Kill
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct Befriend {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for Befriend {
+    fn new() -> Befriend {
+        Befriend { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Befriend
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct Confirm {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for Confirm {
+    fn new() -> Confirm {
+        Confirm { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Confirm
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct Frame {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for Frame {
+    fn new() -> Frame {
+        Frame { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Frame
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct Clean {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for Clean {
+    fn new() -> Clean {
+        Clean { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Clean
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct GunCheck {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for GunCheck {
+    fn new() -> GunCheck {
+        GunCheck { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::GunCheck
+    }
     fn active(&self) -> bool {
         self.active
     }
     fn target(&self) -> &Option<Entity> {
         &self.target
     }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct GiveGun {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for GiveGun {
+    fn new() -> GiveGun {
+        GiveGun { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::GiveGun
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct Shoot {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for Shoot {
+    fn new() -> Shoot {
+        Shoot { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::Shoot
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct ModifierCop {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for ModifierCop {
+    fn new() -> ModifierCop {
+        ModifierCop { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::ModifierCop
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct VanillaCop {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for VanillaCop {
+    fn new() -> VanillaCop {
+        VanillaCop { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::VanillaCop
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
+}
+
+#[derive(Component, Debug)]
+#[storage(VecStorage)]
+pub struct AlignmentRoleCop {
+    active: bool,
+    target: Option<Entity>,
+}
+
+impl Action for AlignmentRoleCop {
+    fn new() -> AlignmentRoleCop {
+        AlignmentRoleCop { active: false, target: None }
+    }
+    fn kind(&self) -> ActionKind {
+        ActionKind::AlignmentRoleCop
+    }
+    fn active(&self) -> bool {
+        self.active
+    }
+    fn target(&self) -> &Option<Entity> {
+        &self.target
+    }
+    fn set(&mut self, active: bool, target: Option<Entity>) {
+        self.active = active;
+        self.target = target;
+    }
 }
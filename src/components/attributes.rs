@@ -1,16 +1,34 @@
 use specs::{Component, VecStorage, NullStorage};
 use std::fmt::Display;
 use derive_display_from_debug::Display;
+use serde::{Serialize, Deserialize};
+use super::super::resources::GameRng;
 
 pub trait ActionStopper {}
 
-#[derive(Component, Debug, Display)]
+#[derive(Component, Clone, Debug, Display, PartialEq, Serialize, Deserialize)]
 #[storage(VecStorage)]
 pub enum Innocence {
     Innocent,
     Guilty,
 }
 
+/// Whether a role has any power, as seen by a vanilla cop
+#[derive(Component, Clone, Copy, Debug, Display, PartialEq, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub enum RoleKind {
+    Vanilla,
+    Power,
+}
+
+/// What investigations see instead of the player's real `I` tonight, e.g. when framed
+#[derive(Debug)]
+pub struct Apparent<I>(pub I);
+
+impl<I> Component for Apparent<I> where I: Send + Sync + 'static {
+    type Storage = VecStorage<Self>;
+}
+
 #[derive(Component, Default, Debug)]
 #[storage(NullStorage)]
 pub struct Gun;
@@ -52,3 +70,50 @@ pub struct Breakthrough;
 #[derive(Component, Default, Debug)]
 #[storage(NullStorage)]
 pub struct Visiting;
+
+/// Signifies that the player's actions reach their target without visiting, so they are never seen
+#[derive(Component, Default, Debug)]
+#[storage(NullStorage)]
+pub struct Astral;
+
+/// Signifies that player's role and faction are hidden when they die
+#[derive(Component, Default, Debug)]
+#[storage(NullStorage)]
+pub struct Cleaned;
+
+/// Signifies that whoever kills the player at night dies with them
+#[derive(Component, Default, Debug)]
+#[storage(NullStorage)]
+pub struct Bomb;
+
+/// Signifies that the player survives night kills
+#[derive(Component, Default, Debug)]
+#[storage(NullStorage)]
+pub struct Bulletproof;
+
+/// How an investigator's results are distorted, without them being told
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub enum Sanity {
+    /// Every result is negative, e.g. innocent or unarmed
+    Naive,
+    /// Every result is positive, e.g. guilty or armed
+    Paranoid,
+    /// Every result is the opposite of the truth
+    Insane,
+    /// Every result is positive or negative at random
+    Random,
+}
+
+impl Sanity {
+    /// What the investigator is told, where `positive` is whether the true result is positive
+    /// Only a random sanity draws from `rng`
+    pub fn apply(&self, positive: bool, rng: &mut GameRng) -> bool {
+        match self {
+            Sanity::Naive    => false,
+            Sanity::Paranoid => true,
+            Sanity::Insane   => !positive,
+            Sanity::Random   => rng.coin(),
+        }
+    }
+}
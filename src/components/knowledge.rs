@@ -0,0 +1,192 @@
+use specs::{Component, VecStorage, Entity};
+use super::{Faction, Group, Role, Modifier, Modifiers};
+use super::attributes::{Innocence, RoleKind, Sanity};
+use super::super::resources::{GameRng, Night};
+use super::super::catalogue::Catalogue;
+
+/// A single piece of information a player has, from setup or from a night result
+/// Players are entities in the world, and `PlayerId`s once handed out by a `Game`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fact<P = Entity> {
+    /// Player is a member of the faction
+    Faction(P, Faction),
+    /// Player checked as the given innocence
+    Alignment(P, Innocence),
+    /// Player has the role
+    Role(P, Role),
+    /// Player is a member of the private group
+    Member(P, Group),
+    /// First player visited the second (or went nowhere) on the night
+    Visited(P, Option<P>, Night),
+    /// Player was found to have a gun, or not
+    Armed(P, bool),
+    /// Player has the modifiers, which may be none
+    Modified(P, Vec<Modifier>),
+    /// Player has a vanilla or a power role
+    Kind(P, RoleKind),
+}
+
+impl<P: Copy> Fact<P> {
+    /// The same fact with each player referred to another way
+    pub fn map_players<Q>(&self, f: impl Fn(P) -> Q) -> Fact<Q> {
+        match self {
+            Fact::Faction(player, faction)       => Fact::Faction(f(*player), faction.clone()),
+            Fact::Alignment(player, innocence)   => Fact::Alignment(f(*player), innocence.clone()),
+            Fact::Role(player, role)             => Fact::Role(f(*player), role.clone()),
+            Fact::Member(player, group)          => Fact::Member(f(*player), group.clone()),
+            Fact::Visited(player, target, night) => Fact::Visited(f(*player), target.map(&f), night.clone()),
+            Fact::Armed(player, armed)           => Fact::Armed(f(*player), *armed),
+            Fact::Modified(player, modifiers)    => Fact::Modified(f(*player), modifiers.clone()),
+            Fact::Kind(player, kind)             => Fact::Kind(f(*player), *kind),
+        }
+    }
+}
+
+/// Information that can be learned about a target by investigating them
+pub trait Observable {
+    fn observe(&self, target: Entity, night: &Night) -> Fact;
+    /// How the information is shown to the player who learned it
+    fn describe(&self, catalogue: &Catalogue) -> String;
+    /// What an investigator with the sanity sees instead, or None if they see the truth
+    fn distort(&self, _sanity: &Sanity, _rng: &mut GameRng) -> Option<Self> where Self: Sized {
+        None
+    }
+}
+
+impl Observable for Innocence {
+    fn observe(&self, target: Entity, _night: &Night) -> Fact {
+        Fact::Alignment(target, self.clone())
+    }
+
+    fn describe(&self, catalogue: &Catalogue) -> String {
+        catalogue.innocence(self)
+    }
+
+    fn distort(&self, sanity: &Sanity, rng: &mut GameRng) -> Option<Self> {
+        if sanity.apply(*self == Innocence::Guilty, rng) {
+            Some(Innocence::Guilty)
+        } else {
+            Some(Innocence::Innocent)
+        }
+    }
+}
+
+impl Observable for Role {
+    fn observe(&self, target: Entity, _night: &Night) -> Fact {
+        Fact::Role(target, self.clone())
+    }
+
+    fn describe(&self, catalogue: &Catalogue) -> String {
+        catalogue.role_name(self)
+    }
+}
+
+impl Observable for Modifiers {
+    fn observe(&self, target: Entity, _night: &Night) -> Fact {
+        Fact::Modified(target, self.0.clone())
+    }
+
+    fn describe(&self, catalogue: &Catalogue) -> String {
+        catalogue.modifiers(&self.0)
+    }
+}
+
+impl Observable for RoleKind {
+    fn observe(&self, target: Entity, _night: &Night) -> Fact {
+        Fact::Kind(target, *self)
+    }
+
+    fn describe(&self, catalogue: &Catalogue) -> String {
+        catalogue.role_kind(self)
+    }
+
+    fn distort(&self, sanity: &Sanity, rng: &mut GameRng) -> Option<Self> {
+        if sanity.apply(*self == RoleKind::Power, rng) {
+            Some(RoleKind::Power)
+        } else {
+            Some(RoleKind::Vanilla)
+        }
+    }
+}
+
+/// Everything a player privately knows, in the order it was learned
+#[derive(Component, Debug, Default)]
+#[storage(VecStorage)]
+pub struct Knowledge(pub Vec<Fact>);
+
+impl Knowledge {
+    /// Record a fact, ignoring it if it is already known
+    pub fn learn(&mut self, fact: Fact) {
+        if !self.0.contains(&fact) {
+            self.0.push(fact);
+        }
+    }
+
+    pub fn facts(&self) -> &[Fact] {
+        &self.0
+    }
+
+    /// Most recently learned faction of the player, if known
+    pub fn faction_of(&self, player: Entity) -> Option<&Faction> {
+        self.0.iter().rev().find_map(|fact| match fact {
+            Fact::Faction(ent, faction) if *ent == player => Some(faction),
+            _ => None,
+        })
+    }
+
+    /// Most recently learned innocence of the player, if known
+    pub fn alignment_of(&self, player: Entity) -> Option<&Innocence> {
+        self.0.iter().rev().find_map(|fact| match fact {
+            Fact::Alignment(ent, inno) if *ent == player => Some(inno),
+            _ => None,
+        })
+    }
+
+    /// Most recently learned role of the player, if known
+    pub fn role_of(&self, player: Entity) -> Option<&Role> {
+        self.0.iter().rev().find_map(|fact| match fact {
+            Fact::Role(ent, role) if *ent == player => Some(role),
+            _ => None,
+        })
+    }
+
+    /// Every player known to be in the faction
+    pub fn members_of(&self, faction: &Faction) -> Vec<Entity> {
+        self.0.iter()
+            .filter_map(|fact| match fact {
+                Fact::Faction(ent, other) if other == faction => Some(*ent),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every player known to be in the private group
+    pub fn members_of_group(&self, group: &Group) -> Vec<Entity> {
+        self.0.iter()
+            .filter_map(|fact| match fact {
+                Fact::Member(ent, other) if other == group => Some(*ent),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Known visits made by the player, as (target, night)
+    pub fn visits_by(&self, player: Entity) -> Vec<(Option<Entity>, &Night)> {
+        self.0.iter()
+            .filter_map(|fact| match fact {
+                Fact::Visited(visitor, target, night) if *visitor == player => Some((*target, night)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Known visitors of the player, as (visitor, night)
+    pub fn visitors_of(&self, player: Entity) -> Vec<(Entity, &Night)> {
+        self.0.iter()
+            .filter_map(|fact| match fact {
+                Fact::Visited(visitor, Some(target), night) if *target == player => Some((*visitor, night)),
+                _ => None,
+            })
+            .collect()
+    }
+}
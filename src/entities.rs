@@ -1,30 +1,90 @@
+use log::error;
 use specs::{World,WorldExt,Entity,EntityBuilder,Builder};
-use super::components::{Name,Faction,Target,Position,Role,Modifier,Modifiers,actions,actions::Action,attributes};
+use super::components::{Name,Faction,Group,Groups,Charges,Dead,DeathCause,NightResult,Target,Role,Modifier,Modifiers,actions,actions::Action,actions::ActionKind,actions::with_action,attributes};
+use super::components::knowledge::{Fact,Knowledge};
+use super::resources::{CurrentNight,CurrentPhase,Phase,Audience,Reveal,Reveals};
+use super::validation::{ActionError,validate};
+use super::roles::{Passive,RoleDefinition};
 
 // certain roles will overwrite the faction
 // e.g. if trying to make a Town Goon, a Mafia Goon will instead be returned
 pub fn create_player(world: &mut World, name: String, faction: Faction, role: Role, modifiers: Vec<Modifier>) -> Entity {
     let player = world.create_entity()
         .base_player(name)
-        .faction(faction);
+        .faction(role.faction_for(faction))
+        .with(role.kind());
     let mut player = give_role(player, role);
     player = give_modifiers(player, modifiers);
-    player.build()
+    let player = player.build();
+    give_setup_knowledge(world, player);
+    player
+}
+
+/// Create a player with a role made up from a definition rather than a built in `Role`
+/// The faction is overwritten if the definition does not allow it
+pub fn create_defined_player(world: &mut World, name: String, faction: Faction, definition: &RoleDefinition, modifiers: Vec<Modifier>) -> Entity {
+    let mut player = world.create_entity()
+        .base_player(name)
+        .faction(definition.faction_for(faction))
+        .with(definition.role())
+        .with(definition.kind());
+    for kind in definition.abilities.iter() {
+        player = player.ability(*kind);
+    }
+    for passive in definition.passives.iter() {
+        player = player.passive(*passive);
+    }
+    if let Some(inno) = &definition.innocence {
+        player = player.with(inno.clone());
+    }
+    if !definition.groups.is_empty() {
+        player = player.with(Groups(definition.groups.clone()));
+    }
+    if !definition.charges.is_empty() {
+        let charges = ActionKind::ALL.iter()
+            .filter_map(|kind| definition.charges.get(kind).map(|uses| (*kind, *uses)))
+            .collect();
+        player = player.with(Charges(charges));
+    }
+    let player = give_modifiers(player, modifiers).build();
+    give_setup_knowledge(world, player);
+    player
 }
 
 pub fn give_role<'a>(player: EntityBuilder<'a>, role: Role) -> EntityBuilder<'a> {
     let player = player.with(role.clone());
     match role {
-        Role::Vanilla     => player.vanilla(),
-        Role::Cop         => player.cop(),
-        Role::Sheriff     => player.cop().detective(),
-        Role::Detective   => player.detective(),
-        Role::Tracker     => player.tracker(),
-        Role::Watcher     => player.watcher(),
-        Role::Roleblocker => player.roleblocker(),
-        Role::Doctor      => player.doctor(),
-        Role::Goon        => player.goon(),
-        Role::Godfather   => player.godfather(),
+        Role::Vanilla          => player.vanilla(),
+        Role::Cop              => player.cop(),
+        Role::Sheriff          => player.cop().detective(),
+        Role::Detective        => player.detective(),
+        Role::Tracker          => player.tracker(),
+        Role::Watcher          => player.watcher(),
+        Role::Roleblocker      => player.roleblocker(),
+        Role::Doctor           => player.doctor(),
+        Role::Vigilante        => player.vigilante(),
+        Role::Bomb             => player.bomb(),
+        Role::Goon             => player.goon(),
+        Role::Godfather        => player.godfather(),
+        Role::Framer           => player.framer(),
+        Role::Janitor          => player.janitor(),
+        Role::Mason            => player.mason(),
+        Role::Neighbor         => player.neighbor(),
+        Role::FriendlyNeighbor => player.friendly_neighbor(),
+        Role::InnocentChild    => player.innocent_child(),
+        Role::SerialKiller     => player.serial_killer(),
+        Role::Jester           => player.vanilla(),
+        Role::Survivor         => player.vanilla(),
+        Role::JackOfAllTrades  => player.jack_of_all_trades(),
+        Role::Gunsmith         => player.gunsmith(),
+        Role::GunDealer        => player.gun_dealer(),
+        Role::GunOwner         => player.gun_owner(),
+        Role::RoleCop          => player.detective(),
+        Role::ModifierCop      => player.modifier_cop(),
+        Role::VanillaCop       => player.vanilla_cop(),
+        Role::AlignmentRoleCop => player.alignment_role_cop(),
+        // custom roles get their mechanics from a `RoleDefinition`, see `create_defined_player`
+        Role::Custom(_)        => player.vanilla(),
     }
 }
 
@@ -34,11 +94,214 @@ pub fn give_modifiers<'a>(player: EntityBuilder<'a>, modifiers: Vec<Modifier>) -
         player_upd = match modifier {
             Modifier::Breakthrough => player_upd.breakthrough(),
             Modifier::Macho        => player_upd.macho(),
+            Modifier::Naive        => player_upd.with(attributes::Sanity::Naive),
+            Modifier::Paranoid     => player_upd.with(attributes::Sanity::Paranoid),
+            Modifier::Insane       => player_upd.with(attributes::Sanity::Insane),
+            Modifier::Random       => player_upd.with(attributes::Sanity::Random),
+            Modifier::Astral       => player_upd.with(attributes::Astral),
         };
     }
     player_upd.with(Modifiers(modifiers))
 }
 
+/// Give the player knowledge of their own role and faction, and introduce them
+/// to (and make them known by) the other members of an informed faction or private group
+pub fn give_setup_knowledge(world: &mut World, player: Entity) {
+    use specs::Join;
+
+    let entities = world.entities();
+    let factions = world.read_storage::<Faction>();
+    let roles = world.read_storage::<Role>();
+    let mut knowledges = world.write_storage::<Knowledge>();
+
+    let mut knowledge = Knowledge::default();
+    let faction = factions.get(player);
+    if let Some(faction) = faction {
+        knowledge.learn(Fact::Faction(player, faction.clone()));
+    }
+    if let Some(role) = roles.get(player) {
+        knowledge.learn(Fact::Role(player, role.clone()));
+    }
+
+    if let Some(faction) = faction.filter(|faction| faction.informed()) {
+        for (other, other_faction, other_role, other_knowledge) in (&entities, &factions, &roles, &mut knowledges).join() {
+            if other != player && other_faction == faction {
+                knowledge.learn(Fact::Faction(other, other_faction.clone()));
+                knowledge.learn(Fact::Role(other, other_role.clone()));
+                other_knowledge.learn(Fact::Faction(player, faction.clone()));
+                if let Some(role) = roles.get(player) {
+                    other_knowledge.learn(Fact::Role(player, role.clone()));
+                }
+            }
+        }
+    }
+
+    let groups = world.read_storage::<Groups>();
+    let player_groups = groups.get(player).map(|groups| groups.0.clone()).unwrap_or_default();
+    for group in player_groups.iter() {
+        knowledge.learn(Fact::Member(player, group.clone()));
+        for (other, other_groups, other_faction, other_knowledge) in (&entities, &groups, &factions, &mut knowledges).join() {
+            if other != player && other_groups.0.contains(group) {
+                knowledge.learn(Fact::Member(other, group.clone()));
+                other_knowledge.learn(Fact::Member(player, group.clone()));
+                if group.reveals_faction() {
+                    knowledge.learn(Fact::Faction(other, other_faction.clone()));
+                    if let Some(faction) = faction {
+                        other_knowledge.learn(Fact::Faction(player, faction.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    if let Err(e) = knowledges.insert(player, knowledge) {
+        error!("error when giving {:?} setup knowledge: {:?}", player, e);
+    }
+}
+
+/// Members of the player's own faction that the player knows about
+pub fn known_teammates(world: &World, player: Entity) -> Vec<Entity> {
+    let knowledges = world.read_storage::<Knowledge>();
+    let factions = world.read_storage::<Faction>();
+    match (knowledges.get(player), factions.get(player)) {
+        (Some(knowledge), Some(faction)) => {
+            knowledge.members_of(faction)
+                .into_iter()
+                .filter(|entity| *entity != player)
+                .collect()
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Members of the private group that the player knows about, including the player
+pub fn group_members(world: &World, player: Entity, group: &Group) -> Vec<Entity> {
+    let knowledges = world.read_storage::<Knowledge>();
+    match knowledges.get(player) {
+        Some(knowledge) => knowledge.members_of_group(group),
+        None => Vec::new(),
+    }
+}
+
+/// Members of the private group that the player knows about and who are still alive
+pub fn living_group_members(world: &World, player: Entity, group: &Group) -> Vec<Entity> {
+    let dead = world.read_storage::<Dead>();
+    group_members(world, player, group)
+        .into_iter()
+        .filter(|member| dead.get(*member).is_none())
+        .collect()
+}
+
+/// Publicly confirm an Innocent Child as town, using up their one reveal
+pub fn confirm_innocent_child(world: &mut World, player: Entity) -> Result<(), ActionError> {
+    validate(world, player, ActionKind::Confirm, None)?;
+    let faction = world.read_storage::<Faction>().get(player).cloned();
+    let role = world.read_storage::<Role>().get(player).cloned();
+    let night = world.read_resource::<CurrentNight>().0.clone();
+    let mut reveals = world.write_resource::<Reveals>();
+    if let Some(faction) = faction {
+        reveals.0.push(Reveal { night: night.clone(), audience: Audience::Everyone, fact: Fact::Faction(player, faction) });
+    }
+    if let Some(role) = role {
+        reveals.0.push(Reveal { night, audience: Audience::Everyone, fact: Fact::Role(player, role) });
+    }
+    drop(reveals);
+    world.write_storage::<actions::Confirm>().remove(player);
+    Ok(())
+}
+
+/// Kill a player outside of night resolution, e.g. by day vote or by the moderator
+pub fn kill_player(world: &mut World, player: Entity, cause: DeathCause, killer: Option<Entity>) {
+    let night = world.read_resource::<CurrentNight>().0.clone();
+    let phase = world.read_resource::<CurrentPhase>().0.clone();
+    if let Err(e) = world.write_storage::<Dead>().insert(player, Dead { night, phase, cause, killer }) {
+        error!("error when {:?} is killed: {:?}", player, e);
+    }
+}
+
+/// Kill a player by day vote, so that their death counts as a lynch
+pub fn lynch_player(world: &mut World, player: Entity) {
+    kill_player(world, player, DeathCause::Lynch, None);
+}
+
+/// Shoot a player during the day, using up the shooter's gun
+/// The shooter no longer counts as armed afterwards, e.g. to gun checks
+/// Nothing happens if no target is given
+pub fn day_shoot(world: &mut World, shooter: Entity, target: Option<Entity>) -> Result<(), ActionError> {
+    validate(world, shooter, ActionKind::Shoot, target)?;
+    if let Some(target) = target {
+        kill_player(world, target, DeathCause::Shot, Some(shooter));
+        world.write_storage::<actions::Shoot>().remove(shooter);
+        world.write_storage::<attributes::Gun>().remove(shooter);
+    }
+    Ok(())
+}
+
+/// Remove a player from the game by moderator decision
+pub fn modkill_player(world: &mut World, player: Entity) {
+    kill_player(world, player, DeathCause::Modkill, None);
+}
+
+/// Submit the player's action for the current phase, replacing any action they submitted before
+/// The action is validated first, and nothing changes if it is rejected
+pub fn submit_action(world: &mut World, player: Entity, kind: ActionKind, target: Option<Entity>) -> Result<(), ActionError> {
+    validate(world, player, kind, target)?;
+    for other in ActionKind::ALL.iter() {
+        set_action(world, player, *other, *other == kind, target);
+    }
+    if let Err(e) = world.write_storage::<Target>().insert(player, Target(target)) {
+        error!("error when {:?} submits a target: {:?}", player, e);
+    }
+    Ok(())
+}
+
+pub(crate) fn set_action(world: &World, player: Entity, kind: ActionKind, active: bool, target: Option<Entity>) {
+    with_action!(kind, A => if let Some(action) = world.write_storage::<A>().get_mut(player) {
+        action.set(active, target);
+    })
+}
+
+/// The action the player has submitted for the current night, and its target
+pub fn submitted_action(world: &World, player: Entity) -> Option<(ActionKind, Option<Entity>)> {
+    ActionKind::ALL.iter().find_map(|kind| with_action!(kind, A => world.read_storage::<A>().get(player)
+        .filter(|action| action.active())
+        .map(|action| (action.kind(), *action.target()))))
+}
+
+/// Withdraw every submitted action, so that each night starts with none
+pub fn clear_actions(world: &mut World) {
+    use specs::Join;
+
+    let players: Vec<Entity> = (&world.entities(), &world.read_storage::<Name>()).join()
+        .map(|(entity, _)| entity)
+        .collect();
+    for player in players {
+        for kind in ActionKind::ALL.iter() {
+            set_action(world, player, *kind, false, None);
+        }
+        if let Err(e) = world.write_storage::<Target>().insert(player, Target(None)) {
+            error!("error when clearing target of {:?}: {:?}", player, e);
+        }
+    }
+}
+
+/// Forget every night result, so that only players who act in the night about to be resolved have one
+pub fn clear_results(world: &mut World) {
+    world.write_storage::<NightResult>().clear();
+}
+
+/// Use up one use of the player's ability, if it is limited
+pub fn spend_charge(world: &mut World, player: Entity, kind: ActionKind) {
+    if let Some(charges) = world.write_storage::<Charges>().get_mut(player) {
+        charges.spend(kind);
+    }
+}
+
+/// End the day, so that the coming night can be resolved
+pub fn end_day(world: &mut World) {
+    world.write_resource::<CurrentPhase>().0 = Phase::Night;
+}
+
 trait PlayerBuilder {
     fn base_player(self, name: String) -> Self;
     fn faction(self, faction: Faction) -> Self;
@@ -49,14 +312,16 @@ impl<'a> PlayerBuilder for EntityBuilder<'a> {
     fn base_player(self, name: String) -> Self {
         self
             .with(Name(name))
-            .with(Position(None))
             .with(Target(None))
     }
 
     fn faction(self, faction: Faction) -> Self {
         let inno = match faction {
-            Faction::Town  => attributes::Innocence::Innocent,
-            Faction::Mafia => attributes::Innocence::Guilty,
+            Faction::Town         => attributes::Innocence::Innocent,
+            Faction::Mafia(_)     => attributes::Innocence::Guilty,
+            Faction::SerialKiller => attributes::Innocence::Guilty,
+            Faction::Jester       => attributes::Innocence::Innocent,
+            Faction::Survivor     => attributes::Innocence::Innocent,
         };
         self
             .with(faction)
@@ -72,8 +337,24 @@ trait RoleBuilder {
     fn watcher(self) -> Self;
     fn roleblocker(self) -> Self;
     fn doctor(self) -> Self;
+    fn vigilante(self) -> Self;
+    fn bomb(self) -> Self;
     fn goon(self) -> Self;
     fn godfather(self) -> Self;
+    fn framer(self) -> Self;
+    fn janitor(self) -> Self;
+    fn mason(self) -> Self;
+    fn neighbor(self) -> Self;
+    fn friendly_neighbor(self) -> Self;
+    fn innocent_child(self) -> Self;
+    fn serial_killer(self) -> Self;
+    fn jack_of_all_trades(self) -> Self;
+    fn gunsmith(self) -> Self;
+    fn gun_dealer(self) -> Self;
+    fn gun_owner(self) -> Self;
+    fn modifier_cop(self) -> Self;
+    fn vanilla_cop(self) -> Self;
+    fn alignment_role_cop(self) -> Self;
 }
 
 impl<'a> RoleBuilder for EntityBuilder<'a> {
@@ -120,9 +401,20 @@ impl<'a> RoleBuilder for EntityBuilder<'a> {
             .with(actions::Save::new())
     }
 
+    fn vigilante(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(attributes::Gun)
+            .with(actions::Kill::new())
+    }
+
+    fn bomb(self) -> Self {
+        self
+            .with(attributes::Bomb)
+    }
+
     fn goon(self) -> Self {
         self
-            .with(Faction::Mafia)
             .with(attributes::Visiting)
             .with(attributes::Gun)
             .with(actions::Kill::new())
@@ -130,13 +422,127 @@ impl<'a> RoleBuilder for EntityBuilder<'a> {
 
     fn godfather(self) -> Self {
         self
-            .with(Faction::Mafia)
             .with(attributes::Visiting)
             .with(attributes::Innocence::Innocent)
             .with(attributes::Undetectable)
             .with(actions::Kill::new())
     }
 
+    fn framer(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(actions::Frame::new())
+    }
+
+    fn janitor(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(actions::Clean::new())
+    }
+
+    fn mason(self) -> Self {
+        self
+            .with(Groups(vec![Group::Masons]))
+    }
+
+    fn neighbor(self) -> Self {
+        self
+            .with(Groups(vec![Group::Neighbors]))
+    }
+
+    fn friendly_neighbor(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(actions::Befriend::new())
+    }
+
+    fn innocent_child(self) -> Self {
+        self
+            .with(actions::Confirm::new())
+    }
+
+    fn serial_killer(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(actions::Kill::new())
+    }
+
+    fn jack_of_all_trades(self) -> Self {
+        self
+            .cop()
+            .doctor()
+            .vigilante()
+            .with(Charges(vec![(ActionKind::Cop, 1), (ActionKind::Save, 1), (ActionKind::Kill, 1)]))
+    }
+
+    fn gunsmith(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(actions::GunCheck::new())
+    }
+
+    fn gun_dealer(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(actions::GiveGun::new())
+    }
+
+    fn gun_owner(self) -> Self {
+        self
+            .with(attributes::Gun)
+            .with(actions::Shoot::new())
+    }
+
+    fn modifier_cop(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(attributes::Gun)
+            .with(actions::ModifierCop::new())
+    }
+
+    fn vanilla_cop(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(attributes::Gun)
+            .with(actions::VanillaCop::new())
+    }
+
+    fn alignment_role_cop(self) -> Self {
+        self
+            .with(attributes::Visiting)
+            .with(attributes::Gun)
+            .with(actions::AlignmentRoleCop::new())
+    }
+
+}
+
+trait AbilityBuilder {
+    fn ability(self, kind: ActionKind) -> Self;
+    fn passive(self, passive: Passive) -> Self;
+}
+
+impl<'a> AbilityBuilder for EntityBuilder<'a> {
+    fn ability(self, kind: ActionKind) -> Self {
+        // every targeted action visits its target
+        let player = if kind.targeted() { self.with(attributes::Visiting) } else { self };
+        let player = with_action!(kind, A => player.with(A::new()));
+        // shooting needs a gun to shoot with
+        if kind == ActionKind::Shoot { player.with(attributes::Gun) } else { player }
+    }
+
+    fn passive(self, passive: Passive) -> Self {
+        match passive {
+            Passive::Gun          => self.with(attributes::Gun),
+            Passive::Undetectable => self.with(attributes::Undetectable),
+            Passive::Uncoppable   => self.with(attributes::Uncoppable),
+            Passive::Untrackable  => self.with(attributes::Untrackable),
+            Passive::Macho        => self.macho(),
+            Passive::Breakthrough => self.breakthrough(),
+            Passive::Bomb         => self.with(attributes::Bomb),
+            Passive::Bulletproof  => self.with(attributes::Bulletproof),
+            Passive::Astral       => self.with(attributes::Astral),
+        }
+    }
 }
 
 trait ModifierBuilder {
@@ -154,3 +560,120 @@ impl<'a> ModifierBuilder for EntityBuilder<'a> {
             .with(attributes::Macho)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::Night;
+    use crate::systems::tests::world;
+
+    fn player(world: &mut World, name: &str, faction: Faction, role: Role) -> Entity {
+        create_player(world, String::from(name), faction, role, Vec::new())
+    }
+
+    #[test]
+    fn masons_know_each_other() {
+        let mut world = world();
+        let mason1 = player(&mut world, "mason1", Faction::Town, Role::Mason);
+        let mason2 = player(&mut world, "mason2", Faction::Town, Role::Mason);
+        let neighbor = player(&mut world, "neighbor", Faction::Town, Role::Neighbor);
+        assert_eq!(group_members(&world, mason1, &Group::Masons), vec![mason1, mason2]);
+        assert_eq!(group_members(&world, mason2, &Group::Masons), vec![mason2, mason1]);
+        assert!(group_members(&world, neighbor, &Group::Masons).is_empty());
+        assert_eq!(group_members(&world, neighbor, &Group::Neighbors), vec![neighbor]);
+    }
+
+    #[test]
+    fn dead_members_leave_the_living_group() {
+        let mut world = world();
+        let mason1 = player(&mut world, "mason1", Faction::Town, Role::Mason);
+        let mason2 = player(&mut world, "mason2", Faction::Town, Role::Mason);
+        lynch_player(&mut world, mason2);
+        assert_eq!(group_members(&world, mason1, &Group::Masons), vec![mason1, mason2]);
+        assert_eq!(living_group_members(&world, mason1, &Group::Masons), vec![mason1]);
+    }
+
+    #[test]
+    fn only_informed_factions_know_their_teammates() {
+        let mut world = world();
+        let goon1 = player(&mut world, "goon1", Faction::Mafia(0), Role::Goon);
+        let goon2 = player(&mut world, "goon2", Faction::Mafia(0), Role::Goon);
+        let town1 = player(&mut world, "town1", Faction::Town, Role::Vanilla);
+        player(&mut world, "town2", Faction::Town, Role::Vanilla);
+        assert_eq!(known_teammates(&world, goon1), vec![goon2]);
+        assert_eq!(known_teammates(&world, goon2), vec![goon1]);
+        assert!(known_teammates(&world, town1).is_empty());
+    }
+
+    #[test]
+    fn lynches_and_modkills_record_their_cause() {
+        let mut world = world();
+        let town1 = player(&mut world, "town1", Faction::Town, Role::Vanilla);
+        let town2 = player(&mut world, "town2", Faction::Town, Role::Vanilla);
+        lynch_player(&mut world, town1);
+        modkill_player(&mut world, town2);
+        let dead = world.read_storage::<Dead>();
+        assert_eq!(dead.get(town1).unwrap().cause, DeathCause::Lynch);
+        assert_eq!(dead.get(town2).unwrap().cause, DeathCause::Modkill);
+        assert!(dead.get(town1).unwrap().killer.is_none());
+        assert!(dead.get(town2).unwrap().killer.is_none());
+    }
+
+    #[test]
+    fn deaths_by_day_are_recorded_on_the_night_to_come() {
+        let mut world = world();
+        world.insert(CurrentNight(Night(2)));
+        world.insert(CurrentPhase(Phase::Day));
+        let town = player(&mut world, "town", Faction::Town, Role::Vanilla);
+        lynch_player(&mut world, town);
+        let dead = world.read_storage::<Dead>();
+        assert_eq!(dead.get(town).unwrap().night, Night(2));
+        assert_eq!(dead.get(town).unwrap().phase, Phase::Day);
+    }
+
+    #[test]
+    fn defined_players_get_the_listed_abilities_and_passives() {
+        let mut world = world();
+        let json = r#"[{
+            "name": "Paranoid Cop",
+            "abilities": ["Cop"],
+            "passives": ["Gun", "Bulletproof"],
+            "groups": ["Masons"],
+            "innocence": "Guilty",
+            "factions": ["Town"]
+        }]"#;
+        let definition = &crate::roles::parse_definitions(json).unwrap()[0];
+        let cop = create_defined_player(&mut world, String::from("cop"), Faction::Mafia(0), definition, Vec::new());
+        assert_eq!(world.read_storage::<Faction>().get(cop), Some(&Faction::Town));
+        assert_eq!(world.read_storage::<Role>().get(cop), Some(&Role::Custom(String::from("Paranoid Cop"))));
+        assert!(world.read_storage::<actions::Cop>().contains(cop));
+        assert!(world.read_storage::<attributes::Visiting>().contains(cop));
+        assert!(!world.read_storage::<actions::Kill>().contains(cop));
+        assert!(world.read_storage::<attributes::Gun>().contains(cop));
+        assert!(world.read_storage::<attributes::Bulletproof>().contains(cop));
+        assert_eq!(world.read_storage::<attributes::Innocence>().get(cop), Some(&attributes::Innocence::Guilty));
+        assert_eq!(group_members(&world, cop, &Group::Masons), vec![cop]);
+        assert_eq!(world.read_storage::<attributes::RoleKind>().get(cop), Some(&attributes::RoleKind::Power));
+    }
+
+    #[test]
+    fn custom_roles_without_a_definition_are_vanilla() {
+        let mut world = world();
+        let player = create_player(&mut world, String::from("custom"), Faction::Town, Role::Custom(String::from("Mystery")), Vec::new());
+        assert_eq!(world.read_storage::<attributes::RoleKind>().get(player), Some(&attributes::RoleKind::Vanilla));
+    }
+
+    #[test]
+    fn gun_owners_shoot_once_by_day() {
+        let mut world = world();
+        world.insert(CurrentPhase(Phase::Day));
+        let owner = player(&mut world, "owner", Faction::Town, Role::GunOwner);
+        let goon = player(&mut world, "goon", Faction::Mafia(0), Role::Goon);
+        let town = player(&mut world, "town", Faction::Town, Role::Vanilla);
+        day_shoot(&mut world, owner, Some(goon)).unwrap();
+        let dead = world.read_storage::<Dead>().get(goon).cloned().unwrap();
+        assert_eq!(dead.cause, DeathCause::Shot);
+        assert_eq!(dead.killer, Some(owner));
+        assert_eq!(day_shoot(&mut world, owner, Some(town)), Err(ActionError::MissingAction));
+    }
+}
@@ -0,0 +1,476 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use specs::{World, WorldExt, Entity, Dispatcher, DispatcherBuilder, RunNow};
+use super::entities;
+use super::components::*;
+use super::components::actions::ActionKind;
+use super::components::attributes::*;
+use super::components::knowledge::{Fact, Knowledge};
+use super::systems::*;
+use super::resources::*;
+use super::validation::{ActionError, has_action};
+use super::save::{self, SaveError, SavedGame};
+use super::messages;
+use super::visits::{self, VisitExport};
+use super::catalogue::Catalogue;
+use super::roles::RoleDefinition;
+
+/// Identifies a player in a `Game`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PlayerId(Entity);
+
+/// Rules chosen for a game before it starts
+#[derive(Clone, Debug, Default)]
+pub struct Setup {
+    pub start: StartRules,
+    pub flips: FlipRule,
+    pub doctors: DoctorRules,
+    /// Templates for all output, in the game's language and with any setup overrides
+    pub messages: Catalogue,
+    /// Whether each night's results are printed to stdout as they resolve
+    pub print_results: bool,
+    /// Seed for the game's randomness, e.g. to replay a game with random sanity
+    /// A seed is picked at random if none is given
+    pub seed: Option<u64>,
+}
+
+/// Public view of a single player
+#[derive(Clone, Debug)]
+pub struct PlayerState {
+    pub id: PlayerId,
+    pub name: String,
+    pub alive: bool,
+}
+
+/// Public view of the whole game
+#[derive(Clone, Debug)]
+pub struct GameState {
+    pub phase: Phase,
+    /// The night being played, or during the day the night to come
+    pub night: Night,
+    pub players: Vec<PlayerState>,
+    pub graveyard: Vec<Grave<PlayerId>>,
+    pub game_over: bool,
+    pub winners: Vec<PlayerId>,
+}
+
+/// A game of mafia, owning its world and the systems that resolve each night
+pub struct Game {
+    world: World,
+    dispatcher: Dispatcher<'static, 'static>,
+    print_results: bool,
+    /// The last night resolved, if any has been
+    last_night: Option<Night>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(Setup::default())
+    }
+}
+
+impl Game {
+    pub fn new(setup: Setup) -> Game {
+        let mut world = World::new();
+        world.register::<Gun>();
+        world.register::<Groups>();
+        world.register::<Modifiers>();
+        world.register::<Charges>();
+        world.register::<actions::Confirm>();
+        world.insert(CurrentNight(setup.start.first_night()));
+        world.insert(CurrentPhase(setup.start.first_phase()));
+        world.insert(setup.start);
+        world.insert(setup.flips);
+        world.insert(setup.doctors);
+        world.insert(PrivateMessages::default());
+        world.insert(setup.messages);
+        world.insert(setup.seed.map_or_else(GameRng::default, GameRng::seeded));
+
+        let mut dispatcher = night_dispatcher(setup.print_results);
+        dispatcher.setup(&mut world);
+
+        Game { world, dispatcher, print_results: setup.print_results, last_night: None }
+    }
+
+    /// Add a player before the game starts
+    /// Certain roles overwrite the faction, see `Role::faction_for`
+    pub fn add_player(&mut self, name: &str, faction: Faction, role: Role, modifiers: Vec<Modifier>) -> PlayerId {
+        PlayerId(entities::create_player(&mut self.world, String::from(name), faction, role, modifiers))
+    }
+
+    /// Add a player with a role made up from a definition, e.g. one loaded from a setup file
+    /// The definition's name and description are used whenever the role is shown
+    pub fn add_defined_player(&mut self, name: &str, faction: Faction, definition: &RoleDefinition, modifiers: Vec<Modifier>) -> PlayerId {
+        self.world.write_resource::<Catalogue>().theme_role(&definition.role(), &definition.name, &definition.description);
+        PlayerId(entities::create_defined_player(&mut self.world, String::from(name), faction, definition, modifiers))
+    }
+
+    /// Find a player by name
+    pub fn player(&self, name: &str) -> Option<PlayerId> {
+        use specs::Join;
+
+        (&self.world.entities(), &self.world.read_storage::<Name>()).join()
+            .find(|(_, other)| other.0 == name)
+            .map(|(entity, _)| PlayerId(entity))
+    }
+
+    pub fn name_of(&self, player: PlayerId) -> Option<String> {
+        self.world.read_storage::<Name>().get(player.0).map(|name| name.0.clone())
+    }
+
+    pub fn role_of(&self, player: PlayerId) -> Option<Role> {
+        self.world.read_storage::<Role>().get(player.0).cloned()
+    }
+
+    pub fn faction_of(&self, player: PlayerId) -> Option<Faction> {
+        self.world.read_storage::<Faction>().get(player.0).cloned()
+    }
+
+    /// How the player's investigations are distorted, which they are not told
+    pub fn sanity_of(&self, player: PlayerId) -> Option<Sanity> {
+        self.world.read_storage::<Sanity>().get(player.0).cloned()
+    }
+
+    /// Submit the player's action for the current phase, replacing any they submitted before
+    pub fn submit_action(&mut self, player: PlayerId, kind: ActionKind, target: Option<PlayerId>) -> Result<(), ActionError> {
+        match kind {
+            ActionKind::Confirm => return entities::confirm_innocent_child(&mut self.world, player.0),
+            ActionKind::Shoot   => {
+                if self.world.read_resource::<Winners>().game_over {
+                    return Err(ActionError::WrongPhase);
+                }
+                entities::day_shoot(&mut self.world, player.0, target.map(|target| target.0))?;
+                ProcessDeaths.run_now(&self.world);
+                CheckWinConditions.run_now(&self.world);
+                self.world.maintain();
+                return Ok(());
+            },
+            _ => (),
+        }
+        entities::submit_action(&mut self.world, player.0, kind, target.map(|target| target.0))
+    }
+
+    /// The action the player has submitted for the current night, and its target
+    pub fn submitted_action(&self, player: PlayerId) -> Option<(ActionKind, Option<PlayerId>)> {
+        entities::submitted_action(&self.world, player.0)
+            .map(|(kind, target)| (kind, target.map(PlayerId)))
+    }
+
+    /// Every kind of action the player has
+    pub fn actions_of(&self, player: PlayerId) -> Vec<ActionKind> {
+        ActionKind::ALL.iter()
+            .filter(|kind| has_action(&self.world, player.0, **kind))
+            .cloned()
+            .collect()
+    }
+
+    /// Uses the player has left of the ability, or None if it is not limited
+    pub fn charges_left(&self, player: PlayerId, kind: ActionKind) -> Option<usize> {
+        self.world.read_storage::<Charges>().get(player.0).and_then(|charges| charges.left(kind))
+    }
+
+    /// Lynch a living player by day vote
+    /// Only one player may be lynched each day, and nobody once the game is over
+    pub fn lynch(&mut self, player: PlayerId) -> Result<(), ActionError> {
+        if self.phase() != Phase::Day || self.world.read_resource::<Winners>().game_over {
+            return Err(ActionError::WrongPhase);
+        }
+        if self.lynched_today() {
+            return Err(ActionError::AlreadyLynched);
+        }
+        if self.world.read_storage::<Name>().get(player.0).is_none() {
+            return Err(ActionError::UnknownPlayer);
+        }
+        if self.world.read_storage::<Dead>().get(player.0).is_some() {
+            return Err(ActionError::DeadTarget);
+        }
+        entities::lynch_player(&mut self.world, player.0);
+        ProcessDeaths.run_now(&self.world);
+        CheckWinConditions.run_now(&self.world);
+        self.world.maintain();
+        Ok(())
+    }
+
+    /// Remove a living player from the game by moderator decision, e.g. for inactivity
+    /// Their death is public straight away, and may end the game
+    pub fn modkill(&mut self, player: PlayerId) -> Result<(), ActionError> {
+        if self.world.read_resource::<Winners>().game_over {
+            return Err(ActionError::WrongPhase);
+        }
+        if self.world.read_storage::<Name>().get(player.0).is_none() {
+            return Err(ActionError::UnknownPlayer);
+        }
+        if self.world.read_storage::<Dead>().get(player.0).is_some() {
+            return Err(ActionError::DeadTarget);
+        }
+        entities::modkill_player(&mut self.world, player.0);
+        ProcessDeaths.run_now(&self.world);
+        CheckWinConditions.run_now(&self.world);
+        self.world.maintain();
+        Ok(())
+    }
+
+    /// Whether a player has been lynched since the last night was resolved
+    /// A lynch is recorded on the night to come, which is the current night during the day
+    fn lynched_today(&self) -> bool {
+        use specs::Join;
+
+        let night = self.world.read_resource::<CurrentNight>().0.clone();
+        self.world.read_storage::<Dead>().join()
+            .any(|dead| dead.cause == DeathCause::Lynch && dead.phase == Phase::Day && dead.night == night)
+    }
+
+    /// Resolve the night and move to the following day, or end the day and move to the night
+    /// Nothing happens once the game is over
+    pub fn advance_phase(&mut self) -> Phase {
+        if !self.world.read_resource::<Winners>().game_over {
+            match self.phase() {
+                Phase::Night => {
+                    let night = self.world.read_resource::<CurrentNight>().0.clone();
+                    let submitted = messages::submitted_actions(&self.world);
+                    entities::clear_results(&mut self.world);
+                    self.world.insert(SubmittedActions(submitted.clone()));
+                    self.dispatcher.dispatch(&self.world);
+                    self.world.maintain();
+                    messages::send_night_messages(&mut self.world, &night, &submitted);
+                    for action in submitted.iter() {
+                        entities::spend_charge(&mut self.world, action.player, action.kind);
+                    }
+                    entities::clear_actions(&mut self.world);
+                    self.last_night = Some(night);
+                },
+                Phase::Day => entities::end_day(&mut self.world),
+            }
+        }
+        self.phase()
+    }
+
+    /// The most recent night to have been resolved, e.g. to look up the messages it sent
+    /// None before the first night is resolved
+    pub fn last_night(&self) -> Option<Night> {
+        self.last_night.clone()
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.world.read_resource::<CurrentPhase>().0.clone()
+    }
+
+    /// The player's result from the last night resolved, or None if they did not act in it
+    pub fn results_for(&self, player: PlayerId) -> Option<NightResult<PlayerId>> {
+        self.world.read_storage::<NightResult>().get(player.0).map(|result| result.map_players(PlayerId))
+    }
+
+    /// Every visit made on the last night resolved
+    pub fn visit_graph(&self) -> VisitGraph<PlayerId> {
+        self.world.read_resource::<VisitGraph>().map_players(PlayerId)
+    }
+
+    /// The last night's visits and kills with players named, to export as DOT or JSON
+    pub fn visit_export(&self) -> VisitExport {
+        visits::export(&self.world)
+    }
+
+    /// Members of the private group that the player knows about, including the player
+    pub fn group_members(&self, player: PlayerId, group: &Group) -> Vec<PlayerId> {
+        entities::group_members(&self.world, player.0, group).into_iter().map(PlayerId).collect()
+    }
+
+    /// Members of the private group that the player knows about and who are still alive
+    pub fn living_group_members(&self, player: PlayerId, group: &Group) -> Vec<PlayerId> {
+        entities::living_group_members(&self.world, player.0, group).into_iter().map(PlayerId).collect()
+    }
+
+    /// Members of the player's own faction that the player knows about
+    pub fn known_teammates(&self, player: PlayerId) -> Vec<PlayerId> {
+        entities::known_teammates(&self.world, player.0).into_iter().map(PlayerId).collect()
+    }
+
+    /// Every problem found while resolving nights so far, for a moderator to look into
+    pub fn diagnostics(&self) -> Vec<Diagnostic<PlayerId>> {
+        self.world.read_resource::<Diagnostics>().0.iter().map(|diagnostic| diagnostic.map_players(PlayerId)).collect()
+    }
+
+    /// Everything the player privately knows
+    pub fn knowledge_of(&self, player: PlayerId) -> Vec<Fact<PlayerId>> {
+        self.world.read_storage::<Knowledge>().get(player.0)
+            .map(|knowledge| knowledge.facts().iter().map(|fact| fact.map_players(PlayerId)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Everything revealed to the player, including public reveals
+    pub fn reveals_for(&self, player: PlayerId) -> Vec<Fact<PlayerId>> {
+        self.world.read_resource::<Reveals>().seen_by(player.0).map(|fact| fact.map_players(PlayerId)).collect()
+    }
+
+    /// Templates used for the game's output, for front ends to render their own text with
+    pub fn catalogue(&self) -> Catalogue {
+        (*self.world.read_resource::<Catalogue>()).clone()
+    }
+
+    /// Change the templates used for output from now on, e.g. to switch language
+    pub fn set_catalogue(&mut self, catalogue: Catalogue) {
+        self.world.insert(catalogue);
+    }
+
+    /// Every private message sent to the player, oldest first
+    pub fn messages_for(&self, player: PlayerId) -> Vec<String> {
+        self.world.read_resource::<PrivateMessages>().sent_to(player.0)
+            .map(|message| message.text.clone())
+            .collect()
+    }
+
+    /// Write each player's private message for the night to its own file in `dir`, named after the player
+    /// Files start with the player's id, and only keep the letters and digits of their name, so that no name can reach outside `dir`
+    /// Returns the files written
+    pub fn write_messages(&self, dir: impl AsRef<Path>, night: &Night) -> io::Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for message in self.world.read_resource::<PrivateMessages>().sent_on(night.clone()) {
+            let name: String = self.name_of(PlayerId(message.player)).unwrap_or_default()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+                .collect();
+            let path = dir.as_ref().join(format!("{}-{}-night-{}.txt", message.player.id(), name, night.0));
+            fs::write(&path, &message.text)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// Write the whole game to a file, to be continued later with `Game::load`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        let saved = save::save(&self.world, self.print_results, self.last_night.clone());
+        fs::write(path, serde_json::to_string_pretty(&saved)?)?;
+        Ok(())
+    }
+
+    /// Continue a game written by `Game::save`
+    pub fn load(path: impl AsRef<Path>) -> Result<Game, SaveError> {
+        let saved: SavedGame = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let mut game = Game::new(Setup {
+            start: saved.start.clone(),
+            flips: saved.flips.clone(),
+            doctors: saved.doctors.clone(),
+            messages: saved.catalogue.clone(),
+            print_results: saved.print_results,
+            seed: Some(saved.rng.seed),
+        });
+        save::load(&mut game.world, &saved)?;
+        game.last_night = saved.last_night.clone();
+        Ok(game)
+    }
+
+    pub fn state(&self) -> GameState {
+        use specs::Join;
+
+        let names = self.world.read_storage::<Name>();
+        let dead = self.world.read_storage::<Dead>();
+        let winners = self.world.read_resource::<Winners>();
+        GameState {
+            phase: self.phase(),
+            night: self.world.read_resource::<CurrentNight>().0.clone(),
+            players: (&self.world.entities(), &names, (&dead).maybe()).join()
+                .map(|(entity, name, dead)| PlayerState {
+                    id: PlayerId(entity),
+                    name: name.0.clone(),
+                    alive: dead.is_none(),
+                })
+                .collect(),
+            graveyard: self.world.read_resource::<Graveyard>().0.iter().map(|grave| grave.map_players(PlayerId)).collect(),
+            game_over: winners.game_over,
+            winners: winners.players.iter().map(|&winner| PlayerId(winner)).collect(),
+        }
+    }
+}
+
+/// Build the dispatcher that resolves a night from submitted actions
+fn night_dispatcher(print_results: bool) -> Dispatcher<'static, 'static> {
+    let mut builder = DispatcherBuilder::new()
+        .with(BlockActions, "blockers", &[])
+        .with(UpdateVisits, "update_visits", &["blockers"])
+        .with(FrameActions, "framers", &["blockers"])
+        .with(InfoActions::<actions::Cop, Innocence, Uncoppable>::new(), "cops", &["framers"])
+        .with(InfoActions::<actions::Detective, Role, Undetectable>::new(), "detectives", &["blockers"])
+        .with(TrackActions, "trackers", &["update_visits"])
+        .with(InfoActions::<actions::ModifierCop, Modifiers, Undetectable>::new(), "modifier_cops", &["blockers"])
+        .with(InfoActions::<actions::VanillaCop, RoleKind, Undetectable>::new(), "vanilla_cops", &["blockers"])
+        .with(AlignmentRoleActions, "alignment_role_cops", &["framers"])
+        .with(WatchActions, "watchers", &["update_visits"])
+        .with(FriendActions, "friends", &["blockers"])
+        .with(GiveGunActions, "dealers", &["blockers"])
+        .with(GunCheckActions, "gunsmiths", &["dealers"])
+        .with(SaveActions, "doctors", &["cops", "detectives", "modifier_cops", "vanilla_cops",
+                                           "alignment_role_cops", "trackers", "watchers", "friends", "gunsmiths"])
+        .with(KillActions, "killers", &["doctors"])
+        .with(CleanActions, "janitors", &["killers"]);
+    if print_results {
+        builder.add(PrintResults, "results", &["janitors"]);
+    }
+    builder.add_barrier();
+    builder
+        .with(RemoveEffects, "remove_effects", &[])
+        .with(ProcessDeaths, "deaths", &[])
+        .with(CheckWinConditions, "win_conditions", &["deaths"])
+        .with(FinishNight, "advance_night", &["win_conditions"])
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game() -> (Game, Vec<PlayerId>) {
+        let mut game = Game::default();
+        let players = [
+            ("goon1", Faction::Mafia(0), Role::Goon),
+            ("goon2", Faction::Mafia(0), Role::Goon),
+            ("mason1", Faction::Town, Role::Mason),
+            ("mason2", Faction::Town, Role::Mason),
+            ("vanilla1", Faction::Town, Role::Vanilla),
+            ("vanilla2", Faction::Town, Role::Vanilla),
+        ].iter()
+            .map(|(name, faction, role)| game.add_player(name, faction.clone(), role.clone(), Vec::new()))
+            .collect();
+        (game, players)
+    }
+
+    #[test]
+    fn modkills_are_public_and_can_end_the_game() {
+        let (mut game, ids) = game();
+        game.modkill(ids[4]).unwrap();
+        let state = game.state();
+        assert!(!state.players[4].alive);
+        assert_eq!(state.graveyard[0].player, ids[4]);
+        assert_eq!(state.graveyard[0].cause, DeathCause::Modkill);
+        assert!(!state.game_over);
+        assert_eq!(game.modkill(ids[4]), Err(ActionError::DeadTarget));
+        game.modkill(ids[2]).unwrap();
+        let state = game.state();
+        assert!(state.game_over);
+        assert_eq!(state.winners, vec![ids[0], ids[1]]);
+        assert_eq!(game.modkill(ids[3]), Err(ActionError::WrongPhase));
+    }
+
+    #[test]
+    fn players_see_their_own_group_and_team() {
+        let (mut game, ids) = game();
+        assert_eq!(game.known_teammates(ids[0]), vec![ids[1]]);
+        assert!(game.known_teammates(ids[4]).is_empty());
+        assert_eq!(game.group_members(ids[2], &Group::Masons), vec![ids[2], ids[3]]);
+        assert!(game.group_members(ids[4], &Group::Masons).is_empty());
+        game.modkill(ids[3]).unwrap();
+        assert_eq!(game.group_members(ids[2], &Group::Masons), vec![ids[2], ids[3]]);
+        assert_eq!(game.living_group_members(ids[2], &Group::Masons), vec![ids[2]]);
+    }
+
+    #[test]
+    fn a_normal_night_reports_no_diagnostics() {
+        let (mut game, ids) = game();
+        game.advance_phase();
+        game.advance_phase();
+        game.submit_action(ids[0], ActionKind::Kill, Some(ids[4])).unwrap();
+        game.advance_phase();
+        assert!(!game.state().players[4].alive);
+        assert!(game.diagnostics().is_empty());
+    }
+}
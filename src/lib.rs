@@ -2,3 +2,10 @@ pub mod entities;
 pub mod components;
 pub mod systems;
 pub mod resources;
+pub mod validation;
+pub mod game;
+pub mod save;
+pub mod messages;
+pub mod catalogue;
+pub mod roles;
+pub mod visits;
@@ -1,51 +1,75 @@
-#![allow(dead_code)]
-extern crate specs;
-
-use specs::{World, WorldExt};
-use specs::DispatcherBuilder;
-
-use scumsim::entities::create_player;
+use scumsim::game::{Game, PlayerId, Setup};
 use scumsim::components::*;
-use scumsim::components::attributes::*;
-use scumsim::systems::*;
-use scumsim::resources::*;
+use scumsim::components::actions::ActionKind;
+use scumsim::catalogue::cause_key;
 
 fn main() {
     env_logger::init();
 
-    let mut world = World::new();
-    world.register::<Faction>();
-    world.register::<Gun>();
-    world.insert(CurrentNight(Night(0)));
-
-    let mut dispatcher = DispatcherBuilder::new()
-        .with(UpdateTargets, "update_targets", &[])
-        .with(UpdateVisits, "update_visits", &["update_targets"])
-        .with(BlockActions, "blockers", &["update_visits"])
-        .with(InfoActions::<actions::Cop, Innocence, Uncoppable>::new(), "cops", &["blockers"])
-        .with(InfoActions::<actions::Detective, Role, Undetectable>::new(), "detectives", &["blockers"])
-        .with(InfoActions::<actions::Track, Position, Untrackable>::new(), "trackers", &["blockers"])
-        //.with(TrackActions, "trackers", &["blockers"])
-        .with(WatchActions, "watchers", &["blockers"])
-        .with(SaveActions, "doctors", &["cops", "detectives", "trackers", "watchers"])
-        .with(KillActions, "killers", &["doctors"])
-        .with(PrintResults, "results", &["killers"])
-        .with(RemoveEffects, "remove_effects", &["results"])
-        .with(ProcessDeaths, "deaths", &["results"])
-        .with(FinishNight, "advance_night", &["deaths"])
-        .build();
-
-    dispatcher.setup(&mut world);
-
-    create_player(&mut world, String::from("player1"), Faction::Town, Role::Detective, vec![Modifier::Breakthrough]);
-    create_player(&mut world, String::from("player2"), Faction::Mafia, Role::Godfather, vec![]);
-    create_player(&mut world, String::from("player3"), Faction::Town, Role::Doctor, vec![]);
-    create_player(&mut world, String::from("player4"), Faction::Mafia, Role::Roleblocker, vec![Modifier::Macho]);
-    create_player(&mut world, String::from("player5"), Faction::Town, Role::Watcher, vec![]);
-    create_player(&mut world, String::from("player6"), Faction::Town, Role::Tracker, vec![]);
-    
-    dispatcher.dispatch(&mut world);
-    world.maintain();
-    dispatcher.dispatch(&mut world);
-    world.maintain();
+    let mut game = Game::new(Setup { print_results: true, ..Setup::default() });
+
+    let player1 = game.add_player("player1", Faction::Town, Role::Detective, vec![Modifier::Breakthrough]);
+    let player2 = game.add_player("player2", Faction::Mafia(0), Role::Godfather, vec![]);
+    let player3 = game.add_player("player3", Faction::Town, Role::Doctor, vec![]);
+    let player4 = game.add_player("player4", Faction::Mafia(0), Role::Roleblocker, vec![Modifier::Macho]);
+    let player5 = game.add_player("player5", Faction::Town, Role::Watcher, vec![]);
+    let player6 = game.add_player("player6", Faction::Town, Role::Tracker, vec![]);
+
+    let night = [
+        (player1, ActionKind::Detective, player2),
+        (player3, ActionKind::Save, player5),
+        (player4, ActionKind::Block, player1),
+        (player5, ActionKind::Watch, player6),
+        (player6, ActionKind::Track, player4),
+    ];
+    submit_all(&mut game, &night);
+    game.advance_phase();
+    game.advance_phase();
+
+    let night = [
+        (player1, ActionKind::Detective, player4),
+        (player2, ActionKind::Kill, player6),
+        (player3, ActionKind::Save, player1),
+        (player4, ActionKind::Block, player3),
+        (player5, ActionKind::Watch, player6),
+        (player6, ActionKind::Track, player2),
+    ];
+    submit_all(&mut game, &night);
+    game.advance_phase();
+
+    print_graveyard(&game);
+    print_winners(&game);
+}
+
+fn submit_all(game: &mut Game, actions: &[(PlayerId, ActionKind, PlayerId)]) {
+    for &(player, kind, target) in actions.iter() {
+        if let Err(e) = game.submit_action(player, kind, Some(target)) {
+            println!("{} cannot {:?}: {}", game.name_of(player).unwrap_or_default(), kind, game.catalogue().action_error(&e));
+        }
+    }
+}
+
+fn print_graveyard(game: &Game) {
+    let catalogue = game.catalogue();
+    for grave in game.state().graveyard.iter() {
+        println!("{}", catalogue.render("grave", &[
+            ("player", &grave.name),
+            ("flip", &catalogue.flip(&grave.flip)),
+            ("time", &catalogue.time(&grave.phase, &grave.night)),
+            ("cause", &catalogue.render(&cause_key(&grave.cause), &[])),
+        ]));
+    }
+}
+
+fn print_winners(game: &Game) {
+    let catalogue = game.catalogue();
+    let state = game.state();
+    if state.game_over {
+        println!("{}", catalogue.render("game_over", &[]));
+    }
+    for &winner in state.winners.iter() {
+        if let (Some(name), Some(faction)) = (game.name_of(winner), game.faction_of(winner)) {
+            println!("{}", catalogue.render("winner", &[("player", &name), ("faction", &catalogue.faction(&faction))]));
+        }
+    }
 }
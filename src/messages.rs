@@ -0,0 +1,112 @@
+use specs::{World, WorldExt, Entity};
+use super::entities;
+use super::catalogue::{Catalogue, action_key, death_key};
+use super::components::{Name, Dead, NightResult};
+use super::components::actions::ActionKind;
+use super::components::knowledge::Fact;
+use super::resources::{Night, Phase, Audience, Reveals, PrivateMessage, PrivateMessages, Submitted};
+
+/// Every action submitted by a player for the current night
+pub fn submitted_actions(world: &World) -> Vec<Submitted> {
+    use specs::Join;
+
+    (&world.entities(), &world.read_storage::<Name>(), !&world.read_storage::<Dead>()).join()
+        .filter_map(|(player, _, ())| entities::submitted_action(world, player)
+            .map(|(kind, target)| Submitted { player, kind, target }))
+        .collect()
+}
+
+fn name_of(world: &World, catalogue: &Catalogue, player: Option<Entity>) -> String {
+    player.and_then(|player| world.read_storage::<Name>().get(player).map(|name| name.0.clone()))
+        .unwrap_or_else(|| catalogue.render("word.nobody", &[]))
+}
+
+/// Write each player's private message for the resolved night, from the actions submitted for it
+/// Players with nothing to be told get no message
+pub fn send_night_messages(world: &mut World, night: &Night, submitted: &[Submitted]) {
+    use specs::Join;
+
+    let players: Vec<Entity> = (&world.entities(), &world.read_storage::<Name>()).join()
+        .map(|(player, _)| player)
+        .collect();
+    let catalogue = world.read_resource::<Catalogue>();
+    let nobody = catalogue.render("word.nobody", &[]);
+    let mut messages = Vec::new();
+    for player in players {
+        let mut lines = Vec::new();
+        for action in submitted.iter().filter(|action| action.player == player) {
+            let results = world.read_storage::<NightResult>();
+            if let Some(result) = results.get(player).filter(|result| result.action == action.kind) {
+                let val = if result.val.is_empty() { &nobody } else { &result.val };
+                lines.push(catalogue.render(&action_key(action.kind, result.success), &[
+                    ("target", &name_of(world, &catalogue, action.target)),
+                    ("result", val),
+                ]));
+            }
+        }
+        let given_gun = submitted.iter()
+            .filter(|action| action.kind == ActionKind::GiveGun && action.target == Some(player))
+            .any(|action| world.read_storage::<NightResult>().get(action.player)
+                .is_some_and(|result| result.action == ActionKind::GiveGun && result.success));
+        if given_gun {
+            lines.push(catalogue.render("received.gun", &[]));
+        }
+        for reveal in world.read_resource::<Reveals>().0.iter()
+            .filter(|reveal| reveal.night == *night && reveal.audience == Audience::Player(player)) {
+            if let Fact::Faction(from, faction) = &reveal.fact {
+                lines.push(catalogue.render("reveal.faction", &[
+                    ("target", &name_of(world, &catalogue, Some(*from))),
+                    ("result", &catalogue.faction(faction)),
+                ]));
+            }
+        }
+        // deaths by day are public, so only tonight's deaths are told privately
+        if let Some(dead) = world.read_storage::<Dead>().get(player)
+            .filter(|dead| dead.phase == Phase::Night && dead.night == *night) {
+            lines.push(catalogue.render(&death_key(&dead.cause), &[]));
+        }
+        if !lines.is_empty() {
+            messages.push(PrivateMessage {
+                night: night.clone(),
+                player,
+                text: lines.join("\n"),
+            });
+        }
+    }
+    drop(catalogue);
+    world.write_resource::<PrivateMessages>().0.extend(messages);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::{Faction, Role};
+    use crate::components::actions::ActionKind;
+    use crate::game::{Game, Setup};
+    use crate::resources::Phase;
+
+    #[test]
+    fn deaths_are_dated_by_their_phase() {
+        let mut game = Game::new(Setup::default());
+        let goon = game.add_player("goon", Faction::Mafia(0), Role::Goon, Vec::new());
+        let lynched = game.add_player("lynched", Faction::Town, Role::Vanilla, Vec::new());
+        let killed = game.add_player("killed", Faction::Town, Role::Vanilla, Vec::new());
+        for name in ["v1", "v2", "v3"].iter() {
+            game.add_player(name, Faction::Town, Role::Vanilla, Vec::new());
+        }
+        game.advance_phase();
+        game.lynch(lynched).unwrap();
+        game.advance_phase();
+        game.submit_action(goon, ActionKind::Kill, Some(killed)).unwrap();
+        game.advance_phase();
+
+        let graveyard = game.state().graveyard;
+        assert_eq!((graveyard[0].phase.clone(), graveyard[0].night.0), (Phase::Day, 1));
+        assert_eq!((graveyard[1].phase.clone(), graveyard[1].night.0), (Phase::Night, 1));
+        let catalogue = game.catalogue();
+        assert_eq!(catalogue.time(&graveyard[0].phase, &graveyard[0].night), "day 1");
+        assert_eq!(catalogue.time(&graveyard[1].phase, &graveyard[1].night), "night 1");
+        // a lynch is public, so it is not told privately with the next night's results
+        assert!(game.messages_for(lynched).is_empty());
+        assert_eq!(game.messages_for(killed), vec![catalogue.render("death.mafia_kill", &[])]);
+    }
+}
@@ -1,5 +1,409 @@
-#[derive(Default, Clone)]
+use std::fmt;
+use log::warn;
+use specs::Entity;
+use serde::{Serialize, Deserialize};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use super::components::{DeathCause, Faction, ResolveError, Role, SaveHistory};
+use super::components::actions::ActionKind;
+use super::validation::ActionError;
+use super::components::knowledge::Fact;
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Night(pub u8);
 
 #[derive(Default)]
 pub struct CurrentNight(pub Night);
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Phase {
+    Day,
+    #[default]
+    Night,
+}
+
+/// Whether it is currently day or night; during the day `CurrentNight` is the night to come
+#[derive(Default)]
+pub struct CurrentPhase(pub Phase);
+
+/// How the game begins, set by the game setup
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StartRules {
+    /// Whether the game begins with a day rather than a night
+    pub day_start: bool,
+    /// Whether a night start begins with night 0 rather than night 1
+    pub night_zero: bool,
+    /// The only action types that may be used on night 0
+    /// Games without a night 0 have no restricted night
+    pub first_night_actions: Vec<ActionKind>,
+}
+
+impl Default for StartRules {
+    fn default() -> Self {
+        StartRules {
+            day_start: false,
+            night_zero: true,
+            first_night_actions: vec![
+                ActionKind::Cop,
+                ActionKind::Detective,
+                ActionKind::Track,
+                ActionKind::Watch,
+                ActionKind::Block,
+                ActionKind::Save,
+                ActionKind::Befriend,
+                ActionKind::Confirm,
+                ActionKind::Frame,
+                ActionKind::Clean,
+                ActionKind::GunCheck,
+                ActionKind::GiveGun,
+                ActionKind::ModifierCop,
+                ActionKind::VanillaCop,
+                ActionKind::AlignmentRoleCop,
+            ],
+        }
+    }
+}
+
+impl StartRules {
+    pub fn first_night(&self) -> Night {
+        if self.night_zero && !self.day_start { Night(0) } else { Night(1) }
+    }
+
+    pub fn first_phase(&self) -> Phase {
+        if self.day_start { Phase::Day } else { Phase::Night }
+    }
+
+    /// Whether an action of the kind may be used on the night
+    pub fn allows(&self, night: &Night, kind: ActionKind) -> bool {
+        night.0 != 0 || self.first_night_actions.contains(&kind)
+    }
+}
+
+/// A problem found by a system while resolving a night, reported instead of panicking
+#[derive(Clone, Debug)]
+pub struct Diagnostic<P = Entity> {
+    pub night: Night,
+    pub system: String,
+    pub error: ResolveError<P>,
+}
+
+impl<P: Copy> Diagnostic<P> {
+    /// The same diagnostic with the player referred to another way
+    pub fn map_players<Q>(&self, f: impl Fn(P) -> Q) -> Diagnostic<Q> {
+        Diagnostic { night: self.night.clone(), system: self.system.clone(), error: self.error.map_players(f) }
+    }
+}
+
+/// Every problem found so far, in order
+#[derive(Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn report(&mut self, night: &Night, system: &str, error: ResolveError) {
+        warn!("{} on night {}: {}", system, night.0, error);
+        self.0.push(Diagnostic {
+            night: night.clone(),
+            system: system.to_string(),
+            error,
+        });
+    }
+}
+
+/// Players who have met their win condition, and whether the game is over
+/// Jesters are added as soon as they are lynched, even if the game continues
+#[derive(Default)]
+pub struct Winners {
+    pub game_over: bool,
+    pub players: Vec<Entity>,
+}
+
+/// Who a revealed fact was shown to
+#[derive(Clone, Debug, PartialEq)]
+pub enum Audience {
+    Everyone,
+    Player(Entity),
+}
+
+/// A fact deliberately shown to other players, on the given night (or the day before it)
+#[derive(Clone, Debug)]
+pub struct Reveal {
+    pub night: Night,
+    pub audience: Audience,
+    pub fact: Fact,
+}
+
+/// An action a player submitted for the night, kept so it can be used after the action is cleared
+#[derive(Clone, Debug)]
+pub struct Submitted {
+    pub player: Entity,
+    pub kind: ActionKind,
+    pub target: Option<Entity>,
+}
+
+/// Every action submitted for the night being resolved
+#[derive(Clone, Debug, Default)]
+pub struct SubmittedActions(pub Vec<Submitted>);
+
+/// One player visiting another through an action
+#[derive(Clone, Debug, PartialEq)]
+pub struct Visit<P = Entity> {
+    pub visitor: P,
+    pub target: P,
+    pub action: ActionKind,
+    /// The visitor was roleblocked, so never arrived
+    pub blocked: bool,
+}
+
+/// A player killed at night by another, as it happened during resolution
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kill<P = Entity> {
+    pub killer: P,
+    pub victim: P,
+    pub cause: DeathCause,
+}
+
+/// Every visit made on a night, rebuilt by `UpdateVisits` once blocks are known
+/// A player may make several visits, and actions that do not visit are left out
+/// Kills are added as they resolve, including those of bombs
+#[derive(Clone, Debug)]
+pub struct VisitGraph<P = Entity> {
+    pub night: Night,
+    pub visits: Vec<Visit<P>>,
+    pub kills: Vec<Kill<P>>,
+}
+
+impl<P> Default for VisitGraph<P> {
+    fn default() -> Self {
+        VisitGraph { night: Night::default(), visits: Vec::new(), kills: Vec::new() }
+    }
+}
+
+impl<P: Copy> VisitGraph<P> {
+    /// The same graph with each player referred to another way
+    pub fn map_players<Q>(&self, f: impl Fn(P) -> Q) -> VisitGraph<Q> {
+        VisitGraph {
+            night: self.night.clone(),
+            visits: self.visits.iter()
+                .map(|visit| Visit { visitor: f(visit.visitor), target: f(visit.target), action: visit.action, blocked: visit.blocked })
+                .collect(),
+            kills: self.kills.iter()
+                .map(|kill| Kill { killer: f(kill.killer), victim: f(kill.victim), cause: kill.cause.clone() })
+                .collect(),
+        }
+    }
+}
+
+impl VisitGraph {
+    /// Visits that were not blocked
+    pub fn arrived(&self) -> impl Iterator<Item = &Visit> {
+        self.visits.iter().filter(|visit| !visit.blocked)
+    }
+
+    /// Everyone the player arrived at, in order
+    pub fn visited_by(&self, player: Entity) -> Vec<Entity> {
+        let mut targets = Vec::new();
+        for visit in self.arrived().filter(|visit| visit.visitor == player) {
+            if !targets.contains(&visit.target) {
+                targets.push(visit.target);
+            }
+        }
+        targets
+    }
+
+    /// Everyone who arrived at the player, in order
+    pub fn visitors_of(&self, player: Entity) -> Vec<Entity> {
+        let mut visitors = Vec::new();
+        for visit in self.arrived().filter(|visit| visit.target == player) {
+            if !visitors.contains(&visit.visitor) {
+                visitors.push(visit.visitor);
+            }
+        }
+        visitors
+    }
+}
+
+/// Every reveal made so far, in order
+/// Public information lives here, separate from the private `NightResult`s
+#[derive(Default)]
+pub struct Reveals(pub Vec<Reveal>);
+
+impl Reveals {
+    /// Facts every player knows
+    pub fn public(&self) -> impl Iterator<Item = &Fact> {
+        self.0.iter()
+            .filter(|reveal| reveal.audience == Audience::Everyone)
+            .map(|reveal| &reveal.fact)
+    }
+
+    /// Facts revealed to the player, including public ones
+    pub fn seen_by(&self, player: Entity) -> impl Iterator<Item = &Fact> {
+        self.0.iter()
+            .filter(move |reveal| reveal.audience == Audience::Everyone || reveal.audience == Audience::Player(player))
+            .map(|reveal| &reveal.fact)
+    }
+}
+
+/// A message sent privately to a player by the moderator after a night
+#[derive(Clone, Debug)]
+pub struct PrivateMessage {
+    pub night: Night,
+    pub player: Entity,
+    pub text: String,
+}
+
+/// Every private message sent so far, in order
+#[derive(Default)]
+pub struct PrivateMessages(pub Vec<PrivateMessage>);
+
+impl PrivateMessages {
+    pub fn sent_to(&self, player: Entity) -> impl Iterator<Item = &PrivateMessage> {
+        self.0.iter().filter(move |message| message.player == player)
+    }
+
+    pub fn sent_on(&self, night: Night) -> impl Iterator<Item = &PrivateMessage> {
+        self.0.iter().filter(move |message| message.night == night)
+    }
+}
+
+/// The game's source of randomness, e.g. for random sanity
+/// Seeded so that a game can be replayed, and saved as its seed and how many draws have been made
+pub struct GameRng {
+    seed: u64,
+    draws: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        GameRng { seed, draws: 0, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// The generator as it was after `draws` draws from `seed`, to continue a saved game
+    pub fn resumed(seed: u64, draws: u64) -> Self {
+        let mut rng = GameRng::seeded(seed);
+        for _ in 0..draws {
+            rng.coin();
+        }
+        rng
+    }
+
+    /// True or false with even odds
+    pub fn coin(&mut self) -> bool {
+        self.draws += 1;
+        self.rng.gen()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::seeded(rand::random())
+    }
+}
+
+/// How much of a dead player is revealed to everyone, set by the game setup
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum FlipRule {
+    #[default]
+    FullRole,
+    AlignmentOnly,
+    NoFlip,
+}
+
+/// What was publicly revealed about a dead player
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Flip {
+    Role(Role, Faction),
+    Alignment(Faction),
+    Unrevealed,
+    Cleaned,
+}
+
+impl fmt::Display for Flip {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Flip::Role(role, faction) => write!(f, "{} {}", faction, role),
+            Flip::Alignment(faction)  => write!(f, "{}", faction),
+            Flip::Unrevealed          => write!(f, "unrevealed"),
+            Flip::Cleaned             => write!(f, "cleaned"),
+        }
+    }
+}
+
+/// Public record of a single death
+#[derive(Clone, Debug)]
+pub struct Grave<P = Entity> {
+    pub player: P,
+    pub name: String,
+    pub flip: Flip,
+    pub cause: DeathCause,
+    pub night: Night,
+    pub phase: Phase,
+}
+
+impl<P: Copy> Grave<P> {
+    /// The same grave with the player referred to another way
+    pub fn map_players<Q>(&self, f: impl Fn(P) -> Q) -> Grave<Q> {
+        Grave {
+            player: f(self.player),
+            name: self.name.clone(),
+            flip: self.flip.clone(),
+            cause: self.cause.clone(),
+            night: self.night.clone(),
+            phase: self.phase.clone(),
+        }
+    }
+}
+
+/// Every death so far, in order
+#[derive(Default)]
+pub struct Graveyard(pub Vec<Grave>);
+
+/// Restrictions on doctor saves, set by the game setup
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DoctorRules {
+    /// Whether a doctor may save themself at all
+    pub self_save: bool,
+    /// Whether a doctor may save the same player on two nights in a row
+    pub consecutive_saves: bool,
+    /// How many times a doctor may save themself, if limited
+    pub max_self_saves: Option<usize>,
+}
+
+impl Default for DoctorRules {
+    fn default() -> Self {
+        DoctorRules {
+            self_save: true,
+            consecutive_saves: true,
+            max_self_saves: None,
+        }
+    }
+}
+
+impl DoctorRules {
+    /// Check the doctor, with their save history, may save the target on the night
+    pub fn check(&self, doctor: Entity, target: Entity, history: Option<&SaveHistory>, night: &Night) -> Result<(), ActionError> {
+        let self_save = doctor == target;
+        if self_save && !self.self_save {
+            return Err(ActionError::SelfTarget);
+        }
+        if let Some(history) = history {
+            if !self.consecutive_saves && history.previous_target(night) == Some(target) {
+                return Err(ActionError::ConsecutiveTarget);
+            }
+            if let Some(max) = self.max_self_saves {
+                if self_save && history.self_saves(doctor) >= max {
+                    return Err(ActionError::NoCharges);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use super::components::{Faction, Group, Role};
+use super::components::actions::ActionKind;
+use super::components::attributes::{Innocence, RoleKind};
+
+/// An attribute a role has without using an action
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Passive {
+    Gun,
+    Undetectable,
+    Uncoppable,
+    Untrackable,
+    Macho,
+    Breakthrough,
+    Bomb,
+    Bulletproof,
+    Astral,
+}
+
+impl Passive {
+    pub const ALL: [Passive; 9] = [
+        Passive::Gun,
+        Passive::Undetectable,
+        Passive::Uncoppable,
+        Passive::Untrackable,
+        Passive::Macho,
+        Passive::Breakthrough,
+        Passive::Bomb,
+        Passive::Bulletproof,
+        Passive::Astral,
+    ];
+}
+
+/// A role made up from abilities and passives, e.g. loaded from a setup file
+/// Roles defined this way have no mechanics beyond what is listed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoleDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// The action components the role has
+    #[serde(default)]
+    pub abilities: Vec<ActionKind>,
+    #[serde(default)]
+    pub passives: Vec<Passive>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+    /// Uses of each limited ability, e.g. `{"Cop": 1}` for a one-shot cop
+    #[serde(default)]
+    pub charges: HashMap<ActionKind, usize>,
+    /// How the role checks to cops, instead of the usual innocence of its faction
+    #[serde(default)]
+    pub innocence: Option<Innocence>,
+    /// The only factions the role may be given to, or any faction if empty
+    /// A mafia entry allows every mafia team
+    #[serde(default)]
+    pub factions: Vec<Faction>,
+}
+
+impl RoleDefinition {
+    pub fn role(&self) -> Role {
+        Role::Custom(self.name.clone())
+    }
+
+    /// A role with no abilities or passives is vanilla, like the built-in roles a group chat does not count
+    pub fn kind(&self) -> RoleKind {
+        if self.abilities.is_empty() && self.passives.is_empty() {
+            RoleKind::Vanilla
+        } else {
+            RoleKind::Power
+        }
+    }
+
+    /// The faction a player with this role ends up in when `requested` is asked for
+    /// A faction the role is not allowed in is replaced by the first one it is
+    pub fn faction_for(&self, requested: Faction) -> Faction {
+        let allowed = self.factions.is_empty() || self.factions.iter().any(|faction| match (faction, &requested) {
+            (Faction::Mafia(_), Faction::Mafia(_)) => true,
+            (faction, requested) => faction == requested,
+        });
+        if allowed {
+            requested
+        } else {
+            self.factions[0].clone()
+        }
+    }
+
+    /// Whether the name refers to this role, ignoring case and spaces
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.replace(' ', "").eq_ignore_ascii_case(&name.replace(' ', ""))
+    }
+}
+
+/// Read role definitions from a JSON list
+pub fn parse_definitions(json: &str) -> serde_json::Result<Vec<RoleDefinition>> {
+    serde_json::from_str(json)
+}
+
+pub fn load_definitions(path: impl AsRef<Path>) -> io::Result<Vec<RoleDefinition>> {
+    parse_definitions(&fs::read_to_string(path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlisted_fields_default_to_empty() {
+        let definitions = parse_definitions(r#"[{ "name": "Paranoid Gun Owner", "passives": ["Gun"] }]"#).unwrap();
+        assert_eq!(definitions.len(), 1);
+        let definition = &definitions[0];
+        assert_eq!(definition.role(), Role::Custom(String::from("Paranoid Gun Owner")));
+        assert_eq!(definition.passives, vec![Passive::Gun]);
+        assert!(definition.abilities.is_empty());
+        assert!(definition.groups.is_empty());
+        assert!(definition.innocence.is_none());
+    }
+
+    #[test]
+    fn names_match_ignoring_case_and_spaces() {
+        let definition = &parse_definitions(r#"[{ "name": "Paranoid Gun Owner" }]"#).unwrap()[0];
+        assert!(definition.is_named("paranoidgunowner"));
+        assert!(definition.is_named("PARANOID GUN OWNER"));
+        assert!(!definition.is_named("gun owner"));
+    }
+
+    #[test]
+    fn disallowed_factions_are_replaced() {
+        let definition = &parse_definitions(r#"[{ "name": "Consort", "factions": [{ "Mafia": 0 }] }]"#).unwrap()[0];
+        assert_eq!(definition.faction_for(Faction::Mafia(1)), Faction::Mafia(1));
+        assert_eq!(definition.faction_for(Faction::Town), Faction::Mafia(0));
+        let anyone = &parse_definitions(r#"[{ "name": "Anyone" }]"#).unwrap()[0];
+        assert_eq!(anyone.faction_for(Faction::SerialKiller), Faction::SerialKiller);
+    }
+}
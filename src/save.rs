@@ -0,0 +1,675 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use serde::{Serialize, Deserialize};
+use specs::{World, WorldExt, Entity, Component, Builder};
+use super::components::*;
+use super::components::actions::{Action, ActionKind, with_action};
+use super::components::attributes::{self, Innocence, RoleKind, Sanity};
+use super::components::knowledge::{Fact, Knowledge};
+use super::resources::*;
+use super::catalogue::Catalogue;
+use super::roles::Passive;
+
+/// Why a game could not be saved or loaded
+#[derive(Debug)]
+pub enum SaveError {
+    /// The file could not be read or written
+    Io(io::Error),
+    /// The file is not a valid saved game
+    Format(serde_json::Error),
+    /// The save refers to a player id that is not in it
+    UnknownPlayer(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(error)         => write!(f, "could not access save file: {}", error),
+            SaveError::Format(error)     => write!(f, "invalid save file: {}", error),
+            SaveError::UnknownPlayer(id) => write!(f, "save refers to unknown player {}", id),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(error: serde_json::Error) -> Self {
+        SaveError::Format(error)
+    }
+}
+
+/// Everything needed to rebuild a game's world, with players referred to by saved id
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub print_results: bool,
+    /// The last night resolved, if any has been
+    pub last_night: Option<Night>,
+    pub start: StartRules,
+    pub flips: FlipRule,
+    pub doctors: DoctorRules,
+    pub catalogue: Catalogue,
+    pub night: Night,
+    pub phase: Phase,
+    pub players: Vec<SavedPlayer>,
+    pub reveals: Vec<SavedReveal>,
+    pub graveyard: Vec<SavedGrave>,
+    pub game_over: bool,
+    pub winners: Vec<u32>,
+    pub diagnostics: Vec<SavedDiagnostic>,
+    pub messages: Vec<SavedMessage>,
+    /// Visits made on the last night resolved
+    pub visits: SavedVisitGraph,
+    pub rng: SavedRng,
+}
+
+/// The seed of the game's `GameRng` and how many draws it has made
+#[derive(Serialize, Deserialize)]
+pub struct SavedRng {
+    pub seed: u64,
+    pub draws: u64,
+}
+
+/// A player and every lasting component they have
+/// Effects that only last for a night are not saved, as games are saved between phases
+#[derive(Serialize, Deserialize)]
+pub struct SavedPlayer {
+    pub id: u32,
+    pub name: String,
+    pub faction: Option<Faction>,
+    pub role: Option<Role>,
+    pub modifiers: Option<Vec<Modifier>>,
+    pub innocence: Option<Innocence>,
+    pub groups: Option<Vec<Group>>,
+    pub passives: Vec<Passive>,
+    /// Whether the player's night actions visit their target
+    pub visiting: bool,
+    /// Whether the player's role was hidden by a janitor
+    pub cleaned: bool,
+    pub actions: Vec<SavedAction>,
+    pub target: Option<Option<u32>>,
+    pub knowledge: Option<Vec<SavedFact>>,
+    pub save_history: Option<Vec<(Night, u32)>>,
+    pub charges: Option<Vec<(ActionKind, usize)>>,
+    pub sanity: Option<Sanity>,
+    pub role_kind: Option<RoleKind>,
+    pub result: Option<SavedResult>,
+    pub dead: Option<SavedDeath>,
+    pub long_dead: bool,
+}
+
+/// An action component and the target it is currently set to
+#[derive(Serialize, Deserialize)]
+pub struct SavedAction {
+    pub kind: ActionKind,
+    pub active: bool,
+    pub target: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum SavedFact {
+    Faction(u32, Faction),
+    Alignment(u32, Innocence),
+    Role(u32, Role),
+    Member(u32, Group),
+    Visited(u32, Option<u32>, Night),
+    Armed(u32, bool),
+    Modified(u32, Vec<Modifier>),
+    Kind(u32, RoleKind),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedResult {
+    pub action: ActionKind,
+    pub success: bool,
+    pub val: String,
+    pub facts: Vec<SavedFact>,
+    /// Player and component name of a `ResolveError::MissingComponent`
+    pub error: Option<(u32, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedDeath {
+    pub night: Night,
+    pub phase: Phase,
+    pub cause: DeathCause,
+    pub killer: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedReveal {
+    pub night: Night,
+    /// None if revealed to everyone
+    pub audience: Option<u32>,
+    pub fact: SavedFact,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedGrave {
+    pub player: u32,
+    pub name: String,
+    pub flip: Flip,
+    pub cause: DeathCause,
+    pub night: Night,
+    pub phase: Phase,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedDiagnostic {
+    pub night: Night,
+    pub system: String,
+    pub player: u32,
+    pub component: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedMessage {
+    pub night: Night,
+    pub player: u32,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedVisitGraph {
+    pub night: Night,
+    pub visits: Vec<SavedVisit>,
+    pub kills: Vec<SavedKill>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedVisit {
+    pub visitor: u32,
+    pub target: u32,
+    pub action: ActionKind,
+    pub blocked: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedKill {
+    pub killer: u32,
+    pub victim: u32,
+    pub cause: DeathCause,
+}
+
+fn id(entity: Entity) -> u32 {
+    entity.id()
+}
+
+fn save_fact(fact: &Fact) -> SavedFact {
+    match fact {
+        Fact::Faction(ent, faction)       => SavedFact::Faction(id(*ent), faction.clone()),
+        Fact::Alignment(ent, inno)        => SavedFact::Alignment(id(*ent), inno.clone()),
+        Fact::Role(ent, role)             => SavedFact::Role(id(*ent), role.clone()),
+        Fact::Member(ent, group)          => SavedFact::Member(id(*ent), group.clone()),
+        Fact::Visited(ent, target, night) => SavedFact::Visited(id(*ent), target.map(id), night.clone()),
+        Fact::Armed(ent, armed)           => SavedFact::Armed(id(*ent), *armed),
+        Fact::Modified(ent, modifiers)    => SavedFact::Modified(id(*ent), modifiers.clone()),
+        Fact::Kind(ent, kind)             => SavedFact::Kind(id(*ent), *kind),
+    }
+}
+
+fn has<C: Component>(world: &World, entity: Entity) -> bool {
+    world.read_storage::<C>().get(entity).is_some()
+}
+
+fn has_passive(world: &World, entity: Entity, passive: Passive) -> bool {
+    match passive {
+        Passive::Gun          => has::<attributes::Gun>(world, entity),
+        Passive::Undetectable => has::<attributes::Undetectable>(world, entity),
+        Passive::Uncoppable   => has::<attributes::Uncoppable>(world, entity),
+        Passive::Untrackable  => has::<attributes::Untrackable>(world, entity),
+        Passive::Macho        => has::<attributes::Macho>(world, entity),
+        Passive::Breakthrough => has::<attributes::Breakthrough>(world, entity),
+        Passive::Bomb         => has::<attributes::Bomb>(world, entity),
+        Passive::Bulletproof  => has::<attributes::Bulletproof>(world, entity),
+        Passive::Astral       => has::<attributes::Astral>(world, entity),
+    }
+}
+
+fn save_passives(world: &World, entity: Entity) -> Vec<Passive> {
+    Passive::ALL.iter()
+        .filter(|passive| has_passive(world, entity, **passive))
+        .copied()
+        .collect()
+}
+
+fn save_actions(world: &World, entity: Entity) -> Vec<SavedAction> {
+    ActionKind::ALL.iter()
+        .filter_map(|kind| with_action!(kind, A => world.read_storage::<A>().get(entity).map(|action| SavedAction {
+            kind: action.kind(),
+            active: action.active(),
+            target: action.target().map(id),
+        })))
+        .collect()
+}
+
+fn save_player(world: &World, entity: Entity, name: &Name) -> SavedPlayer {
+    SavedPlayer {
+        id: id(entity),
+        name: name.0.clone(),
+        faction: world.read_storage::<Faction>().get(entity).cloned(),
+        role: world.read_storage::<Role>().get(entity).cloned(),
+        modifiers: world.read_storage::<Modifiers>().get(entity).map(|mods| mods.0.clone()),
+        innocence: world.read_storage::<Innocence>().get(entity).cloned(),
+        groups: world.read_storage::<Groups>().get(entity).map(|groups| groups.0.clone()),
+        passives: save_passives(world, entity),
+        visiting: has::<attributes::Visiting>(world, entity),
+        cleaned: has::<attributes::Cleaned>(world, entity),
+        actions: save_actions(world, entity),
+        target: world.read_storage::<Target>().get(entity).map(|target| target.0.map(id)),
+        knowledge: world.read_storage::<Knowledge>().get(entity)
+            .map(|knowledge| knowledge.facts().iter().map(save_fact).collect()),
+        save_history: world.read_storage::<SaveHistory>().get(entity)
+            .map(|history| history.0.iter().map(|(night, target)| (night.clone(), id(*target))).collect()),
+        charges: world.read_storage::<Charges>().get(entity).map(|charges| charges.0.clone()),
+        sanity: world.read_storage::<Sanity>().get(entity).cloned(),
+        role_kind: world.read_storage::<RoleKind>().get(entity).cloned(),
+        result: world.read_storage::<NightResult>().get(entity).map(|result| SavedResult {
+            action: result.action,
+            success: result.success,
+            val: result.val.clone(),
+            facts: result.facts.iter().map(save_fact).collect(),
+            error: result.error.as_ref().map(|ResolveError::MissingComponent(ent, component)| (id(*ent), component.clone())),
+        }),
+        dead: world.read_storage::<Dead>().get(entity).map(|dead| SavedDeath {
+            night: dead.night.clone(),
+            phase: dead.phase.clone(),
+            cause: dead.cause.clone(),
+            killer: dead.killer.map(id),
+        }),
+        long_dead: has::<LongDead>(world, entity),
+    }
+}
+
+/// Capture the world, along with whether night results are printed and the last night resolved
+pub fn save(world: &World, print_results: bool, last_night: Option<Night>) -> SavedGame {
+    use specs::Join;
+
+    let players = (&world.entities(), &world.read_storage::<Name>()).join()
+        .map(|(entity, name)| save_player(world, entity, name))
+        .collect();
+    let winners = world.read_resource::<Winners>();
+    SavedGame {
+        print_results,
+        last_night,
+        start: (*world.read_resource::<StartRules>()).clone(),
+        flips: (*world.read_resource::<FlipRule>()).clone(),
+        doctors: (*world.read_resource::<DoctorRules>()).clone(),
+        catalogue: (*world.read_resource::<Catalogue>()).clone(),
+        night: world.read_resource::<CurrentNight>().0.clone(),
+        phase: world.read_resource::<CurrentPhase>().0.clone(),
+        players,
+        reveals: world.read_resource::<Reveals>().0.iter()
+            .map(|reveal| SavedReveal {
+                night: reveal.night.clone(),
+                audience: match reveal.audience {
+                    Audience::Everyone       => None,
+                    Audience::Player(player) => Some(id(player)),
+                },
+                fact: save_fact(&reveal.fact),
+            })
+            .collect(),
+        graveyard: world.read_resource::<Graveyard>().0.iter()
+            .map(|grave| SavedGrave {
+                player: id(grave.player),
+                name: grave.name.clone(),
+                flip: grave.flip.clone(),
+                cause: grave.cause.clone(),
+                night: grave.night.clone(),
+                phase: grave.phase.clone(),
+            })
+            .collect(),
+        game_over: winners.game_over,
+        winners: winners.players.iter().map(|&winner| id(winner)).collect(),
+        diagnostics: world.read_resource::<Diagnostics>().0.iter()
+            .map(|diagnostic| {
+                let ResolveError::MissingComponent(ent, component) = &diagnostic.error;
+                SavedDiagnostic {
+                    night: diagnostic.night.clone(),
+                    system: diagnostic.system.clone(),
+                    player: id(*ent),
+                    component: component.clone(),
+                }
+            })
+            .collect(),
+        messages: world.read_resource::<PrivateMessages>().0.iter()
+            .map(|message| SavedMessage {
+                night: message.night.clone(),
+                player: id(message.player),
+                text: message.text.clone(),
+            })
+            .collect(),
+        rng: {
+            let rng = world.read_resource::<GameRng>();
+            SavedRng { seed: rng.seed(), draws: rng.draws() }
+        },
+        visits: {
+            let graph = world.read_resource::<VisitGraph>();
+            SavedVisitGraph {
+                night: graph.night.clone(),
+                visits: graph.visits.iter()
+                    .map(|visit| SavedVisit {
+                        visitor: id(visit.visitor),
+                        target: id(visit.target),
+                        action: visit.action,
+                        blocked: visit.blocked,
+                    })
+                    .collect(),
+                kills: graph.kills.iter()
+                    .map(|kill| SavedKill {
+                        killer: id(kill.killer),
+                        victim: id(kill.victim),
+                        cause: kill.cause.clone(),
+                    })
+                    .collect(),
+            }
+        },
+    }
+}
+
+/// Saved ids mapped to the entities created for them on load
+struct Players(HashMap<u32, Entity>);
+
+impl Players {
+    fn get(&self, saved: u32) -> Result<Entity, SaveError> {
+        self.0.get(&saved).copied().ok_or(SaveError::UnknownPlayer(saved))
+    }
+
+    fn get_opt(&self, saved: Option<u32>) -> Result<Option<Entity>, SaveError> {
+        saved.map(|saved| self.get(saved)).transpose()
+    }
+
+    fn fact(&self, fact: &SavedFact) -> Result<Fact, SaveError> {
+        Ok(match fact {
+            SavedFact::Faction(ent, faction)       => Fact::Faction(self.get(*ent)?, faction.clone()),
+            SavedFact::Alignment(ent, inno)        => Fact::Alignment(self.get(*ent)?, inno.clone()),
+            SavedFact::Role(ent, role)             => Fact::Role(self.get(*ent)?, role.clone()),
+            SavedFact::Member(ent, group)          => Fact::Member(self.get(*ent)?, group.clone()),
+            SavedFact::Visited(ent, target, night) => Fact::Visited(self.get(*ent)?, self.get_opt(*target)?, night.clone()),
+            SavedFact::Armed(ent, armed)           => Fact::Armed(self.get(*ent)?, *armed),
+            SavedFact::Modified(ent, modifiers)    => Fact::Modified(self.get(*ent)?, modifiers.clone()),
+            SavedFact::Kind(ent, kind)             => Fact::Kind(self.get(*ent)?, *kind),
+        })
+    }
+}
+
+fn insert<C: Component>(world: &World, entity: Entity, component: C) {
+    // Entities were created just before, so insertion cannot fail
+    let _ = world.write_storage::<C>().insert(entity, component);
+}
+
+fn load_action<A: Action + Component>(world: &World, entity: Entity, active: bool, target: Option<Entity>) {
+    let mut action = A::new();
+    action.set(active, target);
+    insert(world, entity, action);
+}
+
+fn load_passive(world: &World, entity: Entity, passive: Passive) {
+    match passive {
+        Passive::Gun          => insert(world, entity, attributes::Gun),
+        Passive::Undetectable => insert(world, entity, attributes::Undetectable),
+        Passive::Uncoppable   => insert(world, entity, attributes::Uncoppable),
+        Passive::Untrackable  => insert(world, entity, attributes::Untrackable),
+        Passive::Macho        => insert(world, entity, attributes::Macho),
+        Passive::Breakthrough => insert(world, entity, attributes::Breakthrough),
+        Passive::Bomb         => insert(world, entity, attributes::Bomb),
+        Passive::Bulletproof  => insert(world, entity, attributes::Bulletproof),
+        Passive::Astral       => insert(world, entity, attributes::Astral),
+    }
+}
+
+fn load_player(world: &World, players: &Players, saved: &SavedPlayer) -> Result<(), SaveError> {
+    let entity = players.get(saved.id)?;
+    insert(world, entity, Name(saved.name.clone()));
+    if let Some(faction) = &saved.faction {
+        insert(world, entity, faction.clone());
+    }
+    if let Some(role) = &saved.role {
+        insert(world, entity, role.clone());
+    }
+    if let Some(modifiers) = &saved.modifiers {
+        insert(world, entity, Modifiers(modifiers.clone()));
+    }
+    if let Some(inno) = &saved.innocence {
+        insert(world, entity, inno.clone());
+    }
+    if let Some(groups) = &saved.groups {
+        insert(world, entity, Groups(groups.clone()));
+    }
+    for passive in &saved.passives {
+        load_passive(world, entity, *passive);
+    }
+    if saved.visiting {
+        insert(world, entity, attributes::Visiting);
+    }
+    if saved.cleaned {
+        insert(world, entity, attributes::Cleaned);
+    }
+    for action in &saved.actions {
+        let target = players.get_opt(action.target)?;
+        with_action!(action.kind, A => load_action::<A>(world, entity, action.active, target));
+    }
+    if let Some(target) = saved.target {
+        insert(world, entity, Target(players.get_opt(target)?));
+    }
+    if let Some(facts) = &saved.knowledge {
+        let facts = facts.iter().map(|fact| players.fact(fact)).collect::<Result<_, _>>()?;
+        insert(world, entity, Knowledge(facts));
+    }
+    if let Some(history) = &saved.save_history {
+        let history = history.iter()
+            .map(|(night, target)| Ok((night.clone(), players.get(*target)?)))
+            .collect::<Result<_, SaveError>>()?;
+        insert(world, entity, SaveHistory(history));
+    }
+    if let Some(charges) = &saved.charges {
+        insert(world, entity, Charges(charges.clone()));
+    }
+    if let Some(sanity) = saved.sanity {
+        insert(world, entity, sanity);
+    }
+    if let Some(kind) = saved.role_kind {
+        insert(world, entity, kind);
+    }
+    if let Some(result) = &saved.result {
+        let error = match &result.error {
+            Some((ent, component)) => Some(ResolveError::MissingComponent(players.get(*ent)?, component.clone())),
+            None => None,
+        };
+        let facts = result.facts.iter().map(|fact| players.fact(fact)).collect::<Result<_, _>>()?;
+        insert(world, entity, NightResult { action: result.action, success: result.success, val: result.val.clone(), facts, error });
+    }
+    if let Some(dead) = &saved.dead {
+        insert(world, entity, Dead {
+            night: dead.night.clone(),
+            phase: dead.phase.clone(),
+            cause: dead.cause.clone(),
+            killer: players.get_opt(dead.killer)?,
+        });
+    }
+    if saved.long_dead {
+        insert(world, entity, LongDead);
+    }
+    Ok(())
+}
+
+/// Recreate the saved players and resources in a world with no players yet
+/// The rules resources are expected to be inserted already, see `SavedGame::start`
+pub fn load(world: &mut World, saved: &SavedGame) -> Result<(), SaveError> {
+    let players = Players(saved.players.iter()
+        .map(|player| (player.id, world.create_entity().build()))
+        .collect());
+    for player in &saved.players {
+        load_player(world, &players, player)?;
+    }
+
+    world.insert(CurrentNight(saved.night.clone()));
+    world.insert(CurrentPhase(saved.phase.clone()));
+    let reveals = saved.reveals.iter()
+        .map(|reveal| Ok(Reveal {
+            night: reveal.night.clone(),
+            audience: match reveal.audience {
+                Some(player) => Audience::Player(players.get(player)?),
+                None         => Audience::Everyone,
+            },
+            fact: players.fact(&reveal.fact)?,
+        }))
+        .collect::<Result<_, SaveError>>()?;
+    world.insert(Reveals(reveals));
+    let graves = saved.graveyard.iter()
+        .map(|grave| Ok(Grave {
+            player: players.get(grave.player)?,
+            name: grave.name.clone(),
+            flip: grave.flip.clone(),
+            cause: grave.cause.clone(),
+            night: grave.night.clone(),
+            phase: grave.phase.clone(),
+        }))
+        .collect::<Result<_, SaveError>>()?;
+    world.insert(Graveyard(graves));
+    world.insert(Winners {
+        game_over: saved.game_over,
+        players: saved.winners.iter().map(|&winner| players.get(winner)).collect::<Result<_, _>>()?,
+    });
+    let diagnostics = saved.diagnostics.iter()
+        .map(|diagnostic| Ok(Diagnostic {
+            night: diagnostic.night.clone(),
+            system: diagnostic.system.clone(),
+            error: ResolveError::MissingComponent(players.get(diagnostic.player)?, diagnostic.component.clone()),
+        }))
+        .collect::<Result<_, SaveError>>()?;
+    world.insert(Diagnostics(diagnostics));
+    let messages = saved.messages.iter()
+        .map(|message| Ok(PrivateMessage {
+            night: message.night.clone(),
+            player: players.get(message.player)?,
+            text: message.text.clone(),
+        }))
+        .collect::<Result<_, SaveError>>()?;
+    world.insert(PrivateMessages(messages));
+    let visits = saved.visits.visits.iter()
+        .map(|visit| Ok(Visit {
+            visitor: players.get(visit.visitor)?,
+            target: players.get(visit.target)?,
+            action: visit.action,
+            blocked: visit.blocked,
+        }))
+        .collect::<Result<_, SaveError>>()?;
+    let kills = saved.visits.kills.iter()
+        .map(|kill| Ok(Kill {
+            killer: players.get(kill.killer)?,
+            victim: players.get(kill.victim)?,
+            cause: kill.cause.clone(),
+        }))
+        .collect::<Result<_, SaveError>>()?;
+    world.insert(VisitGraph { night: saved.visits.night.clone(), visits, kills });
+    world.insert(GameRng::resumed(saved.rng.seed, saved.rng.draws));
+    world.maintain();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::components::{Faction, Modifier, Role};
+    use crate::components::actions::ActionKind;
+    use crate::game::{Game, PlayerId, Setup};
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("scumsim-{}-{}.json", name, std::process::id()))
+    }
+
+    /// Save the game and read the file back, compared as JSON so that map order does not matter
+    fn saved(game: &Game, name: &str) -> serde_json::Value {
+        let path = temp_file(name);
+        game.save(&path).unwrap();
+        let value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        value
+    }
+
+    #[test]
+    fn save_load_save_round_trip() {
+        let mut game = Game::new(Setup { seed: Some(3), ..Setup::default() });
+        let cop = game.add_player("cop", Faction::Town, Role::Cop, vec![Modifier::Random]);
+        let doctor = game.add_player("doctor", Faction::Town, Role::Doctor, Vec::new());
+        let tracker = game.add_player("tracker", Faction::Town, Role::Tracker, Vec::new());
+        let goon = game.add_player("goon", Faction::Mafia(0), Role::Goon, Vec::new());
+        game.add_player("godfather", Faction::Mafia(0), Role::Godfather, Vec::new());
+        let vanilla = game.add_player("vanilla", Faction::Town, Role::Vanilla, Vec::new());
+        game.add_player("mason", Faction::Town, Role::Mason, Vec::new());
+
+        // a night with results and save history
+        game.submit_action(cop, ActionKind::Cop, Some(goon)).unwrap();
+        game.submit_action(doctor, ActionKind::Save, Some(vanilla)).unwrap();
+        game.submit_action(tracker, ActionKind::Track, Some(cop)).unwrap();
+        game.advance_phase();
+        // a lynch in the graveyard, then actions submitted but not yet resolved
+        game.lynch(vanilla).unwrap();
+        game.advance_phase();
+        game.submit_action(goon, ActionKind::Kill, Some(doctor)).unwrap();
+        game.submit_action(doctor, ActionKind::Save, Some(doctor)).unwrap();
+
+        let first = saved(&game, "first");
+        assert!(!first["graveyard"].as_array().unwrap().is_empty());
+        let godfather = first["players"].as_array().unwrap().iter()
+            .find(|player| player["name"] == "godfather")
+            .unwrap();
+        assert_eq!(godfather["passives"], serde_json::json!(["Undetectable"]));
+        assert_eq!(godfather["visiting"], true);
+        let path = temp_file("load");
+        game.save(&path).unwrap();
+        let loaded = Game::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved(&loaded, "second"), first);
+    }
+
+    /// A game with a random sanity cop and no kills, so it runs for as many nights as needed
+    fn random_cop(seed: u64) -> (Game, PlayerId, PlayerId) {
+        let mut game = Game::new(Setup { seed: Some(seed), ..Setup::default() });
+        let cop = game.add_player("cop", Faction::Town, Role::Cop, vec![Modifier::Random]);
+        let goon = game.add_player("goon", Faction::Mafia(0), Role::Goon, Vec::new());
+        for name in ["v1", "v2", "v3"].iter() {
+            game.add_player(name, Faction::Town, Role::Vanilla, Vec::new());
+        }
+        (game, cop, goon)
+    }
+
+    /// The cop's result on each of the next `nights` nights
+    fn cop_results(game: &mut Game, cop: PlayerId, goon: PlayerId, nights: usize) -> Vec<String> {
+        (0..nights)
+            .map(|_| {
+                game.submit_action(cop, ActionKind::Cop, Some(goon)).unwrap();
+                game.advance_phase();
+                let result = game.results_for(cop).unwrap().val;
+                game.advance_phase();
+                result
+            })
+            .collect()
+    }
+
+    #[test]
+    fn random_sanity_replays_from_the_seed() {
+        let (mut first, cop, goon) = random_cop(11);
+        let results = cop_results(&mut first, cop, goon, 12);
+        let (mut second, cop, goon) = random_cop(11);
+        assert_eq!(cop_results(&mut second, cop, goon, 12), results);
+
+        // a loaded game carries on drawing where the saved one left off
+        let (mut saved, cop, goon) = random_cop(11);
+        assert_eq!(cop_results(&mut saved, cop, goon, 5), results[..5]);
+        let path = temp_file("replay");
+        saved.save(&path).unwrap();
+        let mut loaded = Game::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(cop_results(&mut loaded, cop, goon, 7), results[5..]);
+    }
+}
@@ -1,72 +1,62 @@
 use log::error;
 use std::marker::PhantomData;
-use specs::{Component, Read, Write, ReadStorage, WriteStorage, Entities, System};
+use specs::{Component, Entity, Read, Write, ReadStorage, WriteStorage, Entities, System};
 use super::components::*;
 use super::components::actions::Action;
 use super::components::attributes::*;
+use super::components::knowledge::*;
 use super::resources::*;
+use super::catalogue::{Catalogue, cause_key};
 
-/// Update targets of all living players from input
-pub struct UpdateTargets;
-impl<'a> System<'a> for UpdateTargets {
-    type SystemData = (Entities<'a>,
-                       ReadStorage<'a, Name>,
-                       ReadStorage<'a, Dead>,
-                       ReadStorage<'a, Faction>,
-                       WriteStorage<'a, Target>,
-                       Read<'a, CurrentNight>);
-
-    fn run(&mut self, (entities, names, dead, factions, mut targets, night): Self::SystemData) {
-        use specs::Join;
-
-        let new_target_name_maf = String::from(
-            if night.0.0 > 0 {"nastykast"} else {"BlueMarble"}
-        );
-
-        let new_target_name_town = String::from(
-            if night.0.0 > 0 {"TheFranswer"} else {"Red123"}
-        );
-
-        let new_target_maf = (&entities, &names, !&dead).join()
-            .find(|(_, name, ())| name.0 == new_target_name_maf)
-            .map(|(entity, _, ())| entity);
-
-        let new_target_town = (&entities, &names, !&dead).join()
-            .find(|(_, name, ())| name.0 == new_target_name_town)
-            .map(|(entity, _, ())| entity);
+/// Get a component a system needs, as an error to report instead of a panic if it is missing
+fn require<C>(component: Option<&C>, entity: Entity) -> Result<&C, ResolveError> {
+    let name = std::any::type_name::<C>().rsplit("::").next().unwrap_or("component");
+    component.ok_or_else(|| ResolveError::MissingComponent(entity, name.to_string()))
+}
 
-        for (faction,target,()) in (&factions, &mut targets, !&dead).join() {
-            if let Faction::Town = faction {
-                target.0 = new_target_town;
-            } else {
-                target.0 = new_target_maf;
-            }
-        }
+/// The target of an action for tonight's resolution, or None if the player did not submit the action
+/// Actions the rules do not allow tonight are resolved with no target, so that they fail
+fn tonight_target<A: Action>(action: &A, target: &Target, night: &Night, rules: &StartRules) -> Option<Option<Entity>> {
+    if !action.active() {
+        return None;
     }
+    Some(if rules.allows(night, action.kind()) { target.0 } else { None })
+}
+
+/// The players' names as a list, e.g. for a watcher's result
+fn names_of(names: &ReadStorage<Name>, players: &[Entity]) -> String {
+    players.iter()
+        .filter_map(|player| names.get(*player).map(|name| name.0.clone()))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
-/// Update the location of anyone with an active visiting action
+/// Rebuild the `VisitGraph` from the night's submitted actions
+/// Runs once blocks are known, so that blocked visits are marked as never arriving
 pub struct UpdateVisits;
 impl<'a> System<'a> for UpdateVisits {
-    type SystemData = (Entities<'a>,
+    type SystemData = (Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       Read<'a, SubmittedActions>,
                        ReadStorage<'a, Visiting>,
-                       ReadStorage<'a, Target>,
+                       ReadStorage<'a, Astral>,
                        ReadStorage<'a, Blocked>,
-                       WriteStorage<'a, Position>);
+                       Write<'a, VisitGraph>);
 
-    fn run(&mut self, (entities, visiting, targets, blocked, mut positions): Self::SystemData) {
-        use specs::Join;
-        for (entity,target, _) in (&entities, &targets, &visiting).join() {
-            let new_pos = if let Some(_) = blocked.get(entity) {
-                Position(None)
-            } else {
-                Position(target.0)
-            };
-            let res = positions.insert(entity, new_pos);
-            if let Err(e) = res {
-                error!("error when updating position of {:?}: {:?}", entity, e);
-            }
-        }
+    fn run(&mut self, data: Self::SystemData) {
+        let (night, rules, submitted, visiting, astral, blocked, mut graph) = data;
+
+        let visits: Vec<Visit> = submitted.0.iter()
+            .filter(|action| action.kind.visits() && rules.allows(&night.0, action.kind))
+            .filter(|action| visiting.contains(action.player) && !astral.contains(action.player))
+            .filter_map(|action| action.target.map(|target| Visit {
+                visitor: action.player,
+                target,
+                action: action.kind,
+                blocked: blocked.contains(action.player),
+            }))
+            .collect();
+        *graph = VisitGraph { night: night.0.clone(), visits, kills: Vec::new() };
     }
 }
 
@@ -74,6 +64,8 @@ impl<'a> System<'a> for UpdateVisits {
 pub struct BlockActions;
 impl<'a> System<'a> for BlockActions {
     type SystemData = (Entities<'a>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
                        ReadStorage<'a, actions::Block>,
                        ReadStorage<'a, Target>,
                        ReadStorage<'a, Breakthrough>,
@@ -81,13 +73,16 @@ impl<'a> System<'a> for BlockActions {
                        WriteStorage<'a, NightResult>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, blockers, targets, breakthroughs, mut blocked, mut results) = data;
+        let (entities, night, rules, blockers, targets, breakthroughs, mut blocked, mut results) = data;
         use specs::Join;
 
-        for (entity, target, _) in (&entities, &targets, &blockers).join() {
+        for (entity, target, blocker) in (&entities, &targets, &blockers).join() {
+            let Some(target) = tonight_target(blocker, target, &night.0, &rules) else {
+                continue;
+            };
             let success: bool;
-            if let Some(target) = target.0 {
-                if let Some(_) = breakthroughs.get(target) {
+            if let Some(target) = target {
+                if breakthroughs.get(target).is_some() {
                     success = false;
                 } else {
                     success = true;
@@ -100,8 +95,11 @@ impl<'a> System<'a> for BlockActions {
                 success = false;
             }
             let res = results.insert(entity, NightResult {
+                action: blocker.kind(),
                 success,
                 val: String::from("n/a"),
+                facts: Vec::new(),
+                error: None,
             });
             if let Err(e) = res {
                 error!("error when {:?} gets block result: {:?}", entity, e);
@@ -110,9 +108,53 @@ impl<'a> System<'a> for BlockActions {
     }
 }
 
+/// Process all frame actions, making each target appear `Guilty` to cops for the night
+pub struct FrameActions;
+impl<'a> System<'a> for FrameActions {
+    type SystemData = (Entities<'a>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       ReadStorage<'a, actions::Frame>,
+                       ReadStorage<'a, Blocked>,
+                       ReadStorage<'a, Target>,
+                       WriteStorage<'a, Apparent<Innocence>>,
+                       WriteStorage<'a, NightResult>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, night, rules, framers, blocked, targets, mut apparent, mut results) = data;
+        use specs::Join;
+
+        for (entity, target, framer, blocked) in (&entities, &targets, &framers, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(framer, target, &night.0, &rules) else {
+                continue;
+            };
+            let success = match (blocked, target) {
+                (None, Some(target)) => {
+                    let res = apparent.insert(target, Apparent(Innocence::Guilty));
+                    if let Err(e) = res {
+                        error!("error when {:?} is framed: {:?}", target, e);
+                    }
+                    true
+                },
+                (Some(_), _) | (_, None) => false,
+            };
+            let res = results.insert(entity, NightResult {
+                action: framer.kind(),
+                success,
+                val: String::from("n/a"),
+                facts: Vec::new(),
+                error: None,
+            });
+            if let Err(e) = res {
+                error!("error when {:?} gets frame result: {:?}", entity, e);
+            }
+        }
+    }
+}
+
 pub struct InfoActions<A, I, S> where
     A: Action + Component,
-    I: Component + std::fmt::Display,
+    I: Component + Send + Sync + Observable,
     S: ActionStopper + Component {
         _action:  PhantomData<A>,
         _info:    PhantomData<I>,
@@ -121,7 +163,7 @@ pub struct InfoActions<A, I, S> where
 
 impl<A, I, S> InfoActions<A, I, S> where 
     A: Action + Component,
-    I: Component + std::fmt::Display,
+    I: Component + Send + Sync + Observable,
     S: ActionStopper + Component {
 
     pub fn new() -> InfoActions<A,I,S> {
@@ -133,37 +175,81 @@ impl<A, I, S> InfoActions<A, I, S> where
     }
 }
 
+impl<A, I, S> Default for InfoActions<A, I, S> where
+    A: Action + Component,
+    I: Component + Send + Sync + Observable,
+    S: ActionStopper + Component {
+
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a, A, I, S> System<'a> for InfoActions<A, I, S> where
     A: Action + Component,
-    I: Component + std::fmt::Display,
+    I: Component + Send + Sync + Observable,
     S: ActionStopper + Component {
     type SystemData = (Entities<'a>,
                        ReadStorage<'a, A>,
                        ReadStorage<'a, Blocked>,
                        ReadStorage<'a, Target>,
                        ReadStorage<'a, I>,
+                       ReadStorage<'a, Apparent<I>>,
                        ReadStorage<'a, S>,
+                       ReadStorage<'a, Sanity>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       Read<'a, Catalogue>,
+                       Write<'a, GameRng>,
+                       Write<'a, Diagnostics>,
+                       WriteStorage<'a, Knowledge>,
                        WriteStorage<'a, NightResult>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, actions, blocked, targets, info_storage, stoppers, mut results) = data;
+        let (entities, actions, blocked, targets, info_storage, apparent, stoppers, sanity, night, rules, catalogue,
+             mut rng, mut diagnostics, mut knowledge, mut results) = data;
         use specs::Join;
 
         for (entity, target, action, blocked) in (&entities, &targets, &actions, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(action, target, &night.0, &rules) else {
+                continue;
+            };
             // if cop is blocked or if there is no target, fail
-            let (success, info) = match (action.active(), blocked, target.0) {
-                (true, None,Some(target)) => {
-                    if let None = stoppers.get(target) {
-                        (true, info_storage.get(target).unwrap().to_string())
+            let (success, info, fact, error) = match (blocked, target) {
+                (None, Some(target)) => {
+                    if stoppers.get(target).is_none() {
+                        // a temporary override (e.g. from a framer) hides the real info
+                        let info = apparent.get(target)
+                            .map(|apparent| &apparent.0)
+                            .map_or_else(|| require(info_storage.get(target), target), Ok);
+                        match info {
+                            Ok(info) => {
+                                // the investigator is not told their sanity, so they learn the distorted info
+                                let distorted = sanity.get(entity).and_then(|sanity| info.distort(sanity, &mut rng));
+                                let info = distorted.as_ref().unwrap_or(info);
+                                let fact = info.observe(target, &night.0);
+                                if let Some(knowledge) = knowledge.get_mut(entity) {
+                                    knowledge.learn(fact.clone());
+                                }
+                                (true, info.describe(&catalogue), Some(fact), None)
+                            },
+                            Err(error) => {
+                                diagnostics.report(&night.0, "InfoActions", error.clone());
+                                (false, String::from("n/a"), None, Some(error))
+                            },
+                        }
                     } else {
-                        (false, String::from("n/a"))
+                        (false, String::from("n/a"), None, None)
                     }
                 }
-                (true, Some(_), _) | (true, _, None) | (false, _, _) => (false, String::from("n/a")),
+                (Some(_), _) | (_, None) => (false, String::from("n/a"), None, None),
             };
             let res = results.insert(entity, NightResult {
+                action: action.kind(),
                 success,
                 val: info,
+                facts: fact.into_iter().collect(),
+                error,
             });
             if let Err(e) = res {
                 error!("error when {:?} gets result: {:?}", entity, e);
@@ -172,173 +258,199 @@ impl<'a, A, I, S> System<'a> for InfoActions<A, I, S> where
     }
 }
 
-/// Process all cop actions and give results as a `NightResult`
-pub struct CopActions;
-impl<'a> System<'a> for CopActions {
+/// Process all track actions, telling each tracker everyone their target visited
+/// Untrackable targets give no result
+pub struct TrackActions;
+impl<'a> System<'a> for TrackActions {
     type SystemData = (Entities<'a>,
-                       ReadStorage<'a, actions::Cop>,
+                       ReadStorage<'a, actions::Track>,
                        ReadStorage<'a, Blocked>,
                        ReadStorage<'a, Target>,
-                       ReadStorage<'a, Innocence>,
+                       ReadStorage<'a, Untrackable>,
+                       ReadStorage<'a, Name>,
+                       Read<'a, VisitGraph>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       WriteStorage<'a, Knowledge>,
                        WriteStorage<'a, NightResult>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, cops, blocked, targets, inno_storage, mut results) = data;
+        let (entities, trackers, blocked, targets, untrackable, names, graph, night, rules, mut knowledge, mut results) = data;
         use specs::Join;
 
-        for (entity, target, cop, blocked) in (&entities, &targets, &cops, (&blocked).maybe()).join() {
-            // if cop is blocked or if there is no target, fail
-            let (success, inno) = match (cop.active(), blocked, target.0) {
-                (true, None, Some(target)) => (true, inno_storage.get(target).unwrap().to_string()),
-                (true, Some(_), _) | (true, _, None) | (false, _, _) => (false, String::from("n/a")),
+        for (entity, target, tracker, blocked) in (&entities, &targets, &trackers, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(tracker, target, &night.0, &rules) else {
+                continue;
             };
-            let res = results.insert(entity, NightResult {
-                success,
-                val: inno,
-            });
-            if let Err(e) = res {
-                error!("error when {:?} gets cop result: {:?}", entity, e);
-            }
-        }
-    }
-}
-
-/// Process all detective actions and give results as a `NightResult`
-pub struct DetActions;
-impl<'a> System<'a> for DetActions {
-    type SystemData = (Entities<'a>,
-                       ReadStorage<'a, actions::Detective>,
-                       ReadStorage<'a, Blocked>,
-                       ReadStorage<'a, Target>,
-                       ReadStorage<'a, Role>,
-                       ReadStorage<'a, Undetectable>,
-                       WriteStorage<'a, NightResult>);
-
-    fn run(&mut self, data: Self::SystemData) {
-        let (entities, dets, blocked, targets, 
-             role_storage, detfails, mut results) = data;
-        use specs::Join;
-
-        for (entity, target, _, blocked) in (&entities, &targets, &dets, (&blocked).maybe()).join() {
-            let (success,role) = match (blocked,target.0) {
-                (None,Some(target)) => {
-                    if let None = detfails.get(target) {
-                        (true, role_storage.get(target).unwrap().to_string())
+            let (success, visited, facts) = match (blocked, target) {
+                (None, Some(target)) if !untrackable.contains(target) => {
+                    let visited = graph.visited_by(target);
+                    let facts = if visited.is_empty() {
+                        vec![Fact::Visited(target, None, night.0.clone())]
                     } else {
-                        (false, String::from("n/a"))
+                        visited.iter().map(|place| Fact::Visited(target, Some(*place), night.0.clone())).collect()
+                    };
+                    if let Some(knowledge) = knowledge.get_mut(entity) {
+                        for fact in facts.iter() {
+                            knowledge.learn(fact.clone());
+                        }
                     }
-                }
-                (Some(_),_) | (_,None) => {
-                    (false, String::from("n/a"))
-                }
+                    (true, names_of(&names, &visited), facts)
+                },
+                _ => (false, String::from("n/a"), Vec::new()),
             };
             let res = results.insert(entity, NightResult {
+                action: tracker.kind(),
                 success,
-                val: role,
+                val: visited,
+                facts,
+                error: None,
             });
             if let Err(e) = res {
-                error!("error when {:?} gets det result: {:?}", entity, e);
+                error!("error when {:?} gets track result: {:?}", entity, e);
             }
         }
     }
 }
 
-/// Process all cop actions and give results as a `NightResult`
-pub struct TrackActions;
-impl<'a> System<'a> for TrackActions {
+/// Process all watch actions, telling each watcher everyone who visited their target
+/// The watcher is never among the visitors they see
+pub struct WatchActions;
+impl<'a> System<'a> for WatchActions {
     type SystemData = (Entities<'a>,
-                       ReadStorage<'a, actions::Track>,
+                       ReadStorage<'a, actions::Watch>,
                        ReadStorage<'a, Blocked>,
                        ReadStorage<'a, Target>,
-                       ReadStorage<'a, Position>,
                        ReadStorage<'a, Name>,
+                       Read<'a, VisitGraph>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       WriteStorage<'a, Knowledge>,
                        WriteStorage<'a, NightResult>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, trackers, blocked, targets, positions, names, mut results) = data;
+        let (entities, watchers, blocked, targets, names, graph, night, rules, mut knowledge, mut results) = data;
         use specs::Join;
 
-        for (entity, target, _, blocked) in (&entities, &targets, &trackers, (&blocked).maybe()).join() {
-            // if tracker is blocked or if there is no target, fail
-            let (success, pos) = match (blocked,target.0) {
-                (None,Some(target)) => {
-                    if let Some(pos) = positions.get(target).unwrap().0 {
-                        (true, names.get(pos).unwrap().0.clone())
-                    } else {
-                        (true, String::from("nowhere"))
+        for (entity, target, watcher, blocked) in (&entities, &targets, &watchers, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(watcher, target, &night.0, &rules) else {
+                continue;
+            };
+            // if watcher is blocked or if there is no target, fail
+            let (success, visitors, facts) = match (blocked, target) {
+                (None, Some(target)) => {
+                    let visitors: Vec<Entity> = graph.visitors_of(target).into_iter()
+                        .filter(|visitor| *visitor != entity)
+                        .collect();
+                    let facts: Vec<Fact> = visitors.iter()
+                        .map(|visitor| Fact::Visited(*visitor, Some(target), night.0.clone()))
+                        .collect();
+                    if let Some(knowledge) = knowledge.get_mut(entity) {
+                        for fact in facts.iter() {
+                            knowledge.learn(fact.clone());
+                        }
                     }
+                    (true, names_of(&names, &visitors), facts)
                 },
-                (Some(_),_) | (_,None) => (false, String::from("n/a")),
+                (Some(_), _) | (_, None) => (false, String::from("n/a"), Vec::new()),
             };
             let res = results.insert(entity, NightResult {
+                action: watcher.kind(),
                 success,
-                val: pos,
+                val: visitors,
+                facts,
+                error: None,
             });
             if let Err(e) = res {
-                error!("error when {:?} gets track result: {:?}", entity, e);
+                error!("error when {:?} gets watch result: {:?}", entity, e);
             }
         }
     }
 }
 
-/// Process all watch actions and give results as a `NightResult`
-pub struct WatchActions;
-impl<'a> System<'a> for WatchActions {
+/// Process all befriend actions, privately revealing each friendly neighbor's faction to their target
+pub struct FriendActions;
+impl<'a> System<'a> for FriendActions {
     type SystemData = (Entities<'a>,
-                       ReadStorage<'a, actions::Watch>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       ReadStorage<'a, actions::Befriend>,
                        ReadStorage<'a, Blocked>,
                        ReadStorage<'a, Target>,
-                       ReadStorage<'a, Position>,
-                       ReadStorage<'a, Name>,
+                       ReadStorage<'a, Faction>,
+                       WriteStorage<'a, Knowledge>,
+                       Write<'a, Reveals>,
                        WriteStorage<'a, NightResult>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, watchers, blocked, targets, positions, names, mut results) = data;
+        let (entities, night, rules, friends, blocked, targets, factions, mut knowledge, mut reveals, mut results) = data;
         use specs::Join;
 
-        for (entity, target, _, blocked) in (&entities, &targets, &watchers, (&blocked).maybe()).join() {
-            // if watcher is blocked or if there is no target, fail
-            let (success, visitors) = match (blocked,target.0) {
-                (None,Some(target)) => {
-                    let visitors = (&names, &positions).join()
-                        .filter(|(_, pos)| if let Some(pos) = pos.0 {pos == target} else {false})
-                        .map(|(name, _)| name.0.clone())
-                        .collect::<Vec<String>>()
-                        .join(", ");
-                    (true, visitors)
+        for (entity, target, faction, friend, blocked) in (&entities, &targets, &factions, &friends, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(friend, target, &night.0, &rules) else {
+                continue;
+            };
+            let success = match (blocked, target) {
+                (None, Some(target)) => {
+                    let fact = Fact::Faction(entity, faction.clone());
+                    if let Some(knowledge) = knowledge.get_mut(target) {
+                        knowledge.learn(fact.clone());
+                    }
+                    reveals.0.push(Reveal {
+                        night: night.0.clone(),
+                        audience: Audience::Player(target),
+                        fact,
+                    });
+                    true
                 },
-                (Some(_),_) | (_,None) => (false, String::from("n/a")),
+                (Some(_), _) | (_, None) => false,
             };
             let res = results.insert(entity, NightResult {
+                action: friend.kind(),
                 success,
-                val: visitors,
+                val: String::from("n/a"),
+                facts: Vec::new(),
+                error: None,
             });
             if let Err(e) = res {
-                error!("error when {:?} gets cop result: {:?}", entity, e);
+                error!("error when {:?} gets befriend result: {:?}", entity, e);
             }
         }
     }
 }
 
-/// Process all save actions and give results as a `NightResult`
+/// Process all save actions permitted by the `DoctorRules` and give results as a `NightResult`
+/// Each save made is added to the doctor's `SaveHistory`
 pub struct SaveActions;
 impl<'a> System<'a> for SaveActions {
     type SystemData = (Entities<'a>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
                        ReadStorage<'a, actions::Save>,
                        ReadStorage<'a, Blocked>,
                        ReadStorage<'a, Target>,
                        ReadStorage<'a, Macho>,
+                       Read<'a, DoctorRules>,
+                       WriteStorage<'a, SaveHistory>,
                        WriteStorage<'a, Saved>,
                        WriteStorage<'a, NightResult>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, doctors, blocked, targets, macho, mut saved, mut results) = data;
+        let (entities, night, rules, doctors, blocked, targets, macho, doctor_rules, mut history, mut saved, mut results) = data;
         use specs::Join;
 
-        for (entity, target, _, blocked) in (&entities, &targets, &doctors, (&blocked).maybe()).join() {
-            let success = match (blocked,target.0) {
+        for (entity, target, doctor, blocked) in (&entities, &targets, &doctors, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(doctor, target, &night.0, &rules) else {
+                continue;
+            };
+            // saves that break the doctor rules are not made
+            let target = target.filter(|target| doctor_rules.check(entity, *target, history.get(entity), &night.0).is_ok());
+            let success = match (blocked,target) {
                 (None,Some(target)) => {
-                    if let None = macho.get(target) {
+                    if let Ok(entry) = history.entry(entity) {
+                        entry.or_insert_with(SaveHistory::default).0.push((night.0.clone(), target));
+                    }
+                    if macho.get(target).is_none() {
                         let res = saved.insert(target, Saved);
                         if let Err(e) = res {
                             error!("error when {:?} is saved: {:?}", target, e);
@@ -351,8 +463,11 @@ impl<'a> System<'a> for SaveActions {
                 (Some(_),_) | (_,None) => false,
             };
             let res = results.insert(entity, NightResult {
+                action: doctor.kind(),
                 success,
                 val: String::from("n/a"),
+                facts: Vec::new(),
+                error: None,
             });
             if let Err(e) = res {
                 error!("error when {:?} gets save result: {:?}", entity, e);
@@ -362,38 +477,80 @@ impl<'a> System<'a> for SaveActions {
 }
 
 /// Process all kill actions and give results as a `NightResult`
+/// A blocked killer kills nobody, but still uses up their team's kill
 pub struct KillActions;
 impl<'a> System<'a> for KillActions {
     type SystemData = (Entities<'a>,
                        Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
                        ReadStorage<'a, actions::Kill>,
+                       ReadStorage<'a, Faction>,
+                       ReadStorage<'a, Blocked>,
                        ReadStorage<'a, Target>,
                        ReadStorage<'a, Saved>,
+                       ReadStorage<'a, Bulletproof>,
+                       ReadStorage<'a, Bomb>,
                        WriteStorage<'a, Dead>,
+                       Write<'a, VisitGraph>,
                        WriteStorage<'a, NightResult>);
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, night, killers, targets, doctored, mut dead, mut results) = data;
+        let (entities, night, rules, killers, factions, blocked, targets, doctored, bulletproof, bombs, mut dead, mut graph,
+             mut results) = data;
         use specs::Join;
 
-        for (entity, target, _) in (&entities, &targets, &killers).join() {
-            let success = match (night.0.0, target.0) {
-                (0, _) | (_, None) => false,
-                (_, Some(target)) =>  {
-                    if let Some(_) = doctored.get(target) {
+        // factions that share a kill only get one per night, used by the first member with a target
+        let mut used_kills: Vec<Faction> = Vec::new();
+
+        for (entity, target, faction, killer) in (&entities, &targets, (&factions).maybe(), &killers).join() {
+            let Some(target) = tonight_target(killer, target, &night.0, &rules) else {
+                continue;
+            };
+            let shared = faction.filter(|faction| faction.shares_kill());
+            let kill_used = shared.is_some_and(|faction| used_kills.contains(faction));
+            let success = match (target, kill_used) {
+                (None, _) | (_, true) => false,
+                (Some(target), false) =>  {
+                    if let Some(faction) = shared {
+                        used_kills.push(faction.clone());
+                    }
+                    if blocked.contains(entity) || doctored.contains(target) || bulletproof.contains(target) {
                         false
                     } else {
-                        let res = dead.insert(target, Dead(night.0.clone()));
+                        let cause = faction.map_or(DeathCause::Vigilante, DeathCause::of_kill_by);
+                        let res = dead.insert(target, Dead {
+                            night: night.0.clone(),
+                            phase: Phase::Night,
+                            cause: cause.clone(),
+                            killer: Some(entity),
+                        });
                         if let Err(e) = res {
                             error!("error when {:?} is killed: {:?}", target, e);
                         }
+                        graph.kills.push(Kill { killer: entity, victim: target, cause });
+                        // killing a bomb takes the killer with them
+                        if bombs.get(target).is_some() {
+                            let res = dead.insert(entity, Dead {
+                                night: night.0.clone(),
+                                phase: Phase::Night,
+                                cause: DeathCause::Bomb,
+                                killer: Some(target),
+                            });
+                            if let Err(e) = res {
+                                error!("error when {:?} is killed by a bomb: {:?}", entity, e);
+                            }
+                            graph.kills.push(Kill { killer: target, victim: entity, cause: DeathCause::Bomb });
+                        }
                         true
                     }
                 },
             };
             let res = results.insert(entity, NightResult {
+                action: killer.kind(),
                 success,
                 val: String::from("n/a"),
+                facts: Vec::new(),
+                error: None,
             });
             if let Err(e) = res {
                 error!("error when {:?} gets kill result: {:?}", entity, e);
@@ -402,6 +559,236 @@ impl<'a> System<'a> for KillActions {
     }
 }
 
+/// Process all clean actions, hiding the role of a target who died tonight from their death reveal
+/// The janitor privately learns the role they cleaned
+pub struct CleanActions;
+impl<'a> System<'a> for CleanActions {
+    type SystemData = (Entities<'a>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       ReadStorage<'a, actions::Clean>,
+                       ReadStorage<'a, Blocked>,
+                       ReadStorage<'a, Target>,
+                       ReadStorage<'a, Dead>,
+                       ReadStorage<'a, Role>,
+                       Read<'a, Catalogue>,
+                       WriteStorage<'a, Cleaned>,
+                       WriteStorage<'a, Knowledge>,
+                       WriteStorage<'a, NightResult>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, night, rules, janitors, blocked, targets, dead, roles, catalogue, mut cleaned, mut knowledge, mut results) = data;
+        use specs::Join;
+
+        for (entity, target, janitor, blocked) in (&entities, &targets, &janitors, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(janitor, target, &night.0, &rules) else {
+                continue;
+            };
+            let (success, role, facts) = match (blocked, target) {
+                (None, Some(target)) => match (dead.get(target), roles.get(target)) {
+                    (Some(died), Some(role)) if died.phase == Phase::Night && died.night == night.0 => {
+                        if let Err(e) = cleaned.insert(target, Cleaned) {
+                            error!("error when {:?} is cleaned: {:?}", target, e);
+                        }
+                        let fact = Fact::Role(target, role.clone());
+                        if let Some(knowledge) = knowledge.get_mut(entity) {
+                            knowledge.learn(fact.clone());
+                        }
+                        (true, catalogue.role_name(role), vec![fact])
+                    },
+                    _ => (false, String::from("n/a"), Vec::new()),
+                },
+                (Some(_), _) | (_, None) => (false, String::from("n/a"), Vec::new()),
+            };
+            let res = results.insert(entity, NightResult {
+                action: janitor.kind(),
+                success,
+                val: role,
+                facts,
+                error: None,
+            });
+            if let Err(e) = res {
+                error!("error when {:?} gets clean result: {:?}", entity, e);
+            }
+        }
+    }
+}
+
+/// Process all give gun actions, arming each target with a gun they may shoot once during the day
+pub struct GiveGunActions;
+impl<'a> System<'a> for GiveGunActions {
+    type SystemData = (Entities<'a>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       ReadStorage<'a, actions::GiveGun>,
+                       ReadStorage<'a, Blocked>,
+                       ReadStorage<'a, Target>,
+                       WriteStorage<'a, Gun>,
+                       WriteStorage<'a, actions::Shoot>,
+                       WriteStorage<'a, NightResult>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, night, rules, dealers, blocked, targets, mut guns, mut shoots, mut results) = data;
+        use specs::Join;
+
+        for (entity, target, dealer, blocked) in (&entities, &targets, &dealers, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(dealer, target, &night.0, &rules) else {
+                continue;
+            };
+            let success = match (blocked, target) {
+                (None, Some(target)) => {
+                    if let Err(e) = guns.insert(target, Gun) {
+                        error!("error when {:?} is given a gun: {:?}", target, e);
+                    }
+                    if !shoots.contains(target) {
+                        if let Err(e) = shoots.insert(target, actions::Shoot::new()) {
+                            error!("error when {:?} is given a shot: {:?}", target, e);
+                        }
+                    }
+                    true
+                },
+                (Some(_), _) | (_, None) => false,
+            };
+            let res = results.insert(entity, NightResult {
+                action: dealer.kind(),
+                success,
+                val: String::from("n/a"),
+                facts: Vec::new(),
+                error: None,
+            });
+            if let Err(e) = res {
+                error!("error when {:?} gets give gun result: {:?}", entity, e);
+            }
+        }
+    }
+}
+
+/// Process all gun checks, telling each gunsmith whether their target has a gun
+/// A gunsmith with a `Sanity` is told a distorted result
+pub struct GunCheckActions;
+impl<'a> System<'a> for GunCheckActions {
+    type SystemData = (Entities<'a>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       ReadStorage<'a, actions::GunCheck>,
+                       ReadStorage<'a, Blocked>,
+                       ReadStorage<'a, Target>,
+                       ReadStorage<'a, Gun>,
+                       ReadStorage<'a, Sanity>,
+                       Read<'a, Catalogue>,
+                       Write<'a, GameRng>,
+                       WriteStorage<'a, Knowledge>,
+                       WriteStorage<'a, NightResult>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, night, rules, gunsmiths, blocked, targets, guns, sanity, catalogue, mut rng, mut knowledge, mut results) = data;
+        use specs::Join;
+
+        for (entity, target, gunsmith, blocked) in (&entities, &targets, &gunsmiths, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(gunsmith, target, &night.0, &rules) else {
+                continue;
+            };
+            let (success, val, facts) = match (blocked, target) {
+                (None, Some(target)) => {
+                    let armed = guns.contains(target);
+                    let armed = sanity.get(entity).map_or(armed, |sanity| sanity.apply(armed, &mut rng));
+                    let fact = Fact::Armed(target, armed);
+                    if let Some(knowledge) = knowledge.get_mut(entity) {
+                        knowledge.learn(fact.clone());
+                    }
+                    (true, catalogue.render(if armed { "gun.armed" } else { "gun.unarmed" }, &[]), vec![fact])
+                },
+                (Some(_), _) | (_, None) => (false, String::from("n/a"), Vec::new()),
+            };
+            let res = results.insert(entity, NightResult {
+                action: gunsmith.kind(),
+                success,
+                val,
+                facts,
+                error: None,
+            });
+            if let Err(e) = res {
+                error!("error when {:?} gets gun check result: {:?}", entity, e);
+            }
+        }
+    }
+}
+
+/// Process all alignment-role checks, telling each cop both the alignment and the role of their target
+/// Uncoppable or undetectable targets give no result, and a `Sanity` only distorts the alignment
+pub struct AlignmentRoleActions;
+impl<'a> System<'a> for AlignmentRoleActions {
+    type SystemData = (Entities<'a>,
+                       ReadStorage<'a, actions::AlignmentRoleCop>,
+                       ReadStorage<'a, Blocked>,
+                       ReadStorage<'a, Target>,
+                       ReadStorage<'a, Innocence>,
+                       ReadStorage<'a, Apparent<Innocence>>,
+                       ReadStorage<'a, Role>,
+                       ReadStorage<'a, Uncoppable>,
+                       ReadStorage<'a, Undetectable>,
+                       ReadStorage<'a, Sanity>,
+                       Read<'a, CurrentNight>,
+                       Read<'a, StartRules>,
+                       Read<'a, Catalogue>,
+                       Write<'a, GameRng>,
+                       Write<'a, Diagnostics>,
+                       WriteStorage<'a, Knowledge>,
+                       WriteStorage<'a, NightResult>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, cops, blocked, targets, innocence, apparent, roles, uncoppable, undetectable, sanity, night, rules,
+             catalogue, mut rng, mut diagnostics, mut knowledge, mut results) = data;
+        use specs::Join;
+
+        for (entity, target, cop, blocked) in (&entities, &targets, &cops, (&blocked).maybe()).join() {
+            let Some(target) = tonight_target(cop, target, &night.0, &rules) else {
+                continue;
+            };
+            let (success, val, facts, error) = match (blocked, target) {
+                (None, Some(target)) if !uncoppable.contains(target) && !undetectable.contains(target) => {
+                    // a framer changes the alignment seen, but not the role
+                    let inno = apparent.get(target)
+                        .map(|apparent| &apparent.0)
+                        .map_or_else(|| require(innocence.get(target), target), Ok);
+                    match inno.and_then(|inno| Ok((inno, require(roles.get(target), target)?))) {
+                        Ok((inno, role)) => {
+                            let distorted = sanity.get(entity).and_then(|sanity| inno.distort(sanity, &mut rng));
+                            let inno = distorted.as_ref().unwrap_or(inno);
+                            let facts = vec![inno.observe(target, &night.0), role.observe(target, &night.0)];
+                            if let Some(knowledge) = knowledge.get_mut(entity) {
+                                for fact in facts.iter() {
+                                    knowledge.learn(fact.clone());
+                                }
+                            }
+                            let val = catalogue.render("report.alignment_role", &[
+                                ("alignment", &inno.describe(&catalogue)),
+                                ("role", &role.describe(&catalogue)),
+                            ]);
+                            (true, val, facts, None)
+                        },
+                        Err(error) => {
+                            diagnostics.report(&night.0, "AlignmentRoleActions", error.clone());
+                            (false, String::from("n/a"), Vec::new(), Some(error))
+                        },
+                    }
+                },
+                _ => (false, String::from("n/a"), Vec::new(), None),
+            };
+            let res = results.insert(entity, NightResult {
+                action: cop.kind(),
+                success,
+                val,
+                facts,
+                error,
+            });
+            if let Err(e) = res {
+                error!("error when {:?} gets alignment-role result: {:?}", entity, e);
+            }
+        }
+    }
+}
+
 /// Print night results
 pub struct PrintResults;
 impl<'a> System<'a> for PrintResults {
@@ -412,70 +799,133 @@ impl<'a> System<'a> for PrintResults {
                        ReadStorage<'a, Target>,
                        ReadStorage<'a, NightResult>,
                        ReadStorage<'a, Dead>,
-                       ReadStorage<'a, LongDead>);
+                       ReadStorage<'a, LongDead>,
+                       Read<'a, Catalogue>,
+                       Write<'a, Diagnostics>);
 
     fn run(&mut self, data : Self::SystemData) {
-        let (night, names, modifiers, roles, targets, results, dead, longdead) = data;
+        let (night, names, modifiers, roles, targets, results, dead, longdead, catalogue, mut diagnostics) = data;
         use specs::Join;
 
-        println!("Night {} results:", night.0.0);
+        println!("{}", catalogue.render("results.header", &[("night", &night.0.0.to_string())]));
         for (name, modifier, role, target, result, dead, ()) in 
             (&names, &modifiers, &roles, &targets, &results, (&dead).maybe(), !&longdead).join() {
                 let target_name = if let Some(ent) = target.0 {
-                    names.get(ent).unwrap().0.clone()
+                    match require(names.get(ent), ent) {
+                        Ok(name) => name.0.clone(),
+                        Err(error) => {
+                            diagnostics.report(&night.0, "PrintResults", error);
+                            String::from("unknown")
+                        },
+                    }
                 } else {
-                    String::from("nobody")
+                    catalogue.render("word.nobody", &[])
                 };
                 let mut modifier = modifier.0
                     .iter()
                     .map(|modifier|modifier.to_string())
                     .collect::<Vec<String>>()
                     .join(" ");
-                if modifier.len() > 0 {
+                if !modifier.is_empty() {
                     modifier.push(' ');
                 }
-                println!("{}{} {} targets {} - {} - {}", modifier, role, name.0, target_name, if result.success { "success" } else { "fail" }, result.val);
-                if let Some(_) = dead {
-                    println!("{} {} died", role, name.0);
+                let outcome = catalogue.render(if result.success { "word.success" } else { "word.failure" }, &[]);
+                println!("{}", catalogue.render("results.line", &[
+                    ("modifiers", &modifier),
+                    ("role", &catalogue.role_name(role)),
+                    ("player", &name.0),
+                    ("target", &target_name),
+                    ("outcome", &outcome),
+                    ("result", &result.val),
+                ]));
+                if let Some(dead) = dead {
+                    println!("{}", catalogue.render("results.died", &[
+                        ("role", &catalogue.role_name(role)),
+                        ("player", &name.0),
+                        ("cause", &catalogue.render(&cause_key(&dead.cause), &[])),
+                    ]));
                 }
             }
     }
 }
 
-/// Remove Blocked, Saved, etc. component from anyone who received it tonight
+/// Remove Blocked, Saved, framed innocence etc. component from anyone who received it tonight
 pub struct RemoveEffects;
 impl<'a> System<'a> for RemoveEffects {
     type SystemData = (Entities<'a>,
                        WriteStorage<'a, Blocked>,
-                       WriteStorage<'a, Saved>);
+                       WriteStorage<'a, Saved>,
+                       WriteStorage<'a, Apparent<Innocence>>);
 
-    fn run(&mut self, (entities, mut blocked, mut saved): Self::SystemData) {
+    fn run(&mut self, (entities, mut blocked, mut saved, mut framed): Self::SystemData) {
         use specs::Join;
 
         for entity in (&entities).join() {
-            if let Some(_) = blocked.get(entity) {
+            if blocked.get(entity).is_some() {
                 blocked.remove(entity);
             }
-            if let Some(_) = saved.get(entity) {
+            if saved.get(entity).is_some() {
                 saved.remove(entity);
             }
+            if framed.get(entity).is_some() {
+                framed.remove(entity);
+            }
         }
     }
 }
 
 /// Make the dead people (`Dead`) `LongDead` and set `Target`s of dead people to `None`
+/// Each newly dead player is added to the `Graveyard` and flipped publicly according to the `FlipRule`,
+/// unless they were `Cleaned`
 pub struct ProcessDeaths;
 impl<'a> System<'a> for ProcessDeaths {
     type SystemData = (Entities<'a>,
+                       Read<'a, FlipRule>,
+                       ReadStorage<'a, Name>,
                        ReadStorage<'a, Dead>,
+                       ReadStorage<'a, Role>,
+                       ReadStorage<'a, Faction>,
+                       ReadStorage<'a, Cleaned>,
+                       Write<'a, Graveyard>,
+                       Write<'a, Reveals>,
                        WriteStorage<'a, LongDead>,
                        WriteStorage<'a, Target>);
 
-    fn run(&mut self, (entities, dead, mut longdead, mut targets): Self::SystemData) {
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, flip_rule, names, dead, roles, factions, cleaned,
+             mut graveyard, mut reveals, mut longdead, mut targets) = data;
         use specs::Join;
 
-        for (entity, _) in (&entities, &dead).join() {
-            if let None = longdead.get(entity) {
+        for (entity, died) in (&entities, &dead).join() {
+            if longdead.get(entity).is_none() {
+                let flip = match (cleaned.get(entity), &*flip_rule, roles.get(entity), factions.get(entity)) {
+                    (Some(_), _, _, _) => Flip::Cleaned,
+                    (None, FlipRule::FullRole, Some(role), Some(faction)) => Flip::Role(role.clone(), faction.clone()),
+                    (None, FlipRule::FullRole, _, Some(faction)) | (None, FlipRule::AlignmentOnly, _, Some(faction)) => {
+                        Flip::Alignment(faction.clone())
+                    },
+                    (None, _, _, _) => Flip::Unrevealed,
+                };
+                let revealed = match &flip {
+                    Flip::Role(role, faction) => vec![Fact::Role(entity, role.clone()), Fact::Faction(entity, faction.clone())],
+                    Flip::Alignment(faction) => vec![Fact::Faction(entity, faction.clone())],
+                    Flip::Unrevealed | Flip::Cleaned => Vec::new(),
+                };
+                for fact in revealed {
+                    reveals.0.push(Reveal {
+                        night: died.night.clone(),
+                        audience: Audience::Everyone,
+                        fact,
+                    });
+                }
+                graveyard.0.push(Grave {
+                    player: entity,
+                    name: names.get(entity).map(|name| name.0.clone()).unwrap_or_default(),
+                    flip,
+                    cause: died.cause.clone(),
+                    night: died.night.clone(),
+                    phase: died.phase.clone(),
+                });
                 if let Err(e) = longdead.insert(entity, LongDead) {
                     error!("error when making {:?} LongDead: {:?}", entity, e);
                 }
@@ -487,12 +937,530 @@ impl<'a> System<'a> for ProcessDeaths {
     }
 }
 
-/// Advance to the next Night
+/// Evaluate every faction's win condition and record all winners in `Winners`
+/// Town and each Mafia team win as a faction, Serial Killers win alone,
+/// Jesters win if lynched and Survivors win if alive when the game ends
+/// The game also ends once no killer is left, as a draw if no town player is left either
+pub struct CheckWinConditions;
+impl<'a> System<'a> for CheckWinConditions {
+    type SystemData = (Entities<'a>,
+                       ReadStorage<'a, Faction>,
+                       ReadStorage<'a, Dead>,
+                       Write<'a, Winners>);
+
+    fn run(&mut self, (entities, factions, dead, mut winners): Self::SystemData) {
+        use specs::Join;
+
+        let alive = (&entities, &factions, !&dead).join()
+            .map(|(entity, faction, ())| (entity, faction))
+            .collect::<Vec<_>>();
+        let killers = alive.iter()
+            .filter(|(_, faction)| faction.kill_capable())
+            .collect::<Vec<_>>();
+
+        let mut winning_factions = Vec::new();
+        let mut winning_players = Vec::new();
+
+        // town wins once every kill-capable player is dead, as long as one of them lives to see it
+        let town_alive = alive.iter().any(|(_, faction)| **faction == Faction::Town);
+        if killers.is_empty() && town_alive {
+            winning_factions.push(Faction::Town);
+        }
+
+        // a mafia team wins at parity, once every opposing killer is dead
+        for (_, faction) in killers.iter() {
+            if let Faction::Mafia(_) = faction {
+                let members = killers.iter().filter(|(_, other)| other == faction).count();
+                if killers.len() == members && members * 2 >= alive.len() && !winning_factions.contains(*faction) {
+                    winning_factions.push((*faction).clone());
+                }
+            }
+        }
+
+        // a serial killer wins alone, once they are the only killer left with at most one other player
+        for (entity, faction) in killers.iter() {
+            if **faction == Faction::SerialKiller && killers.len() == 1 && alive.len() <= 2 {
+                winning_players.push(*entity);
+            }
+        }
+
+        let game_over = killers.is_empty() || !winning_factions.is_empty() || !winning_players.is_empty();
+
+        for (entity, faction, dead) in (&entities, &factions, (&dead).maybe()).join() {
+            let won = match faction {
+                Faction::Town | Faction::Mafia(_) => winning_factions.contains(faction),
+                Faction::SerialKiller => false,
+                Faction::Jester => dead.is_some_and(|dead| dead.cause == DeathCause::Lynch),
+                Faction::Survivor => game_over && dead.is_none(),
+            };
+            if won {
+                winning_players.push(entity);
+            }
+        }
+
+        winners.game_over = game_over;
+        winners.players = winning_players;
+    }
+}
+
+/// Advance to the next Night, and to the day before it
 pub struct FinishNight;
 impl <'a> System<'a> for FinishNight {
-    type SystemData = Write<'a, CurrentNight>;
+    type SystemData = (Write<'a, CurrentNight>,
+                       Write<'a, CurrentPhase>);
 
-    fn run(&mut self, mut night: Self::SystemData) {
+    fn run(&mut self, (mut night, mut phase): Self::SystemData) {
         night.0 = Night(night.0.0 + 1);
+        phase.0 = Phase::Day;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use specs::{World, WorldExt, Entity, DispatcherBuilder, RunNow};
+    use super::*;
+    use crate::components::{Faction, Modifier, Role};
+    use crate::components::actions::ActionKind;
+    use crate::entities::{create_player, kill_player, lynch_player, set_action};
+    use crate::resources::Winners;
+
+    /// A world with every system's storage and resources set up, as a game's would be
+    pub(crate) fn world() -> World {
+        let mut world = World::new();
+        world.register::<Gun>();
+        world.register::<Groups>();
+        world.register::<Modifiers>();
+        world.register::<Charges>();
+        world.register::<actions::Confirm>();
+        DispatcherBuilder::new()
+            .with(UpdateVisits, "update_visits", &[])
+            .with(BlockActions, "blockers", &[])
+            .with(FrameActions, "framers", &[])
+            .with(GiveGunActions, "dealers", &[])
+            .with(GunCheckActions, "gunsmiths", &[])
+            .with(InfoActions::<actions::Cop, Innocence, Uncoppable>::new(), "cops", &[])
+            .with(InfoActions::<actions::Detective, Role, Undetectable>::new(), "detectives", &[])
+            .with(InfoActions::<actions::ModifierCop, Modifiers, Undetectable>::new(), "modifier_cops", &[])
+            .with(InfoActions::<actions::VanillaCop, RoleKind, Undetectable>::new(), "vanilla_cops", &[])
+            .with(AlignmentRoleActions, "alignment_role_cops", &[])
+            .with(TrackActions, "trackers", &[])
+            .with(WatchActions, "watchers", &[])
+            .with(FriendActions, "friends", &[])
+            .with(SaveActions, "doctors", &[])
+            .with(KillActions, "killers", &[])
+            .with(CleanActions, "janitors", &[])
+            .with(PrintResults, "results", &[])
+            .with(RemoveEffects, "remove_effects", &[])
+            .with(ProcessDeaths, "deaths", &[])
+            .with(CheckWinConditions, "win_conditions", &[])
+            .with(FinishNight, "advance_night", &[])
+            .build()
+            .setup(&mut world);
+        world
+    }
+
+    fn players(world: &mut World, players: &[(&str, Faction, Role)]) -> Vec<Entity> {
+        players.iter()
+            .map(|(name, faction, role)| create_player(world, name.to_string(), faction.clone(), role.clone(), Vec::new()))
+            .collect()
+    }
+
+    /// Lynch the player and check the win conditions, returning (game over, winners)
+    fn lynch(world: &mut World, player: Entity) -> (bool, Vec<Entity>) {
+        lynch_player(world, player);
+        CheckWinConditions.run_now(world);
+        let winners = world.read_resource::<Winners>();
+        (winners.game_over, winners.players.clone())
+    }
+
+    #[test]
+    fn mafia_wins_at_parity() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("town1", Faction::Town, Role::Vanilla),
+            ("town2", Faction::Town, Role::Vanilla),
+            ("town3", Faction::Town, Role::Vanilla),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        assert_eq!(lynch(&mut world, ids[0]), (false, Vec::new()));
+        assert_eq!(lynch(&mut world, ids[1]), (true, vec![ids[3]]));
+    }
+
+    #[test]
+    fn serial_killer_wins_in_the_last_two() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("town1", Faction::Town, Role::Vanilla),
+            ("town2", Faction::Town, Role::Vanilla),
+            ("sk", Faction::SerialKiller, Role::SerialKiller),
+        ]);
+        assert_eq!(lynch(&mut world, ids[0]), (true, vec![ids[2]]));
+    }
+
+    #[test]
+    fn jester_wins_when_lynched() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("jester", Faction::Jester, Role::Jester),
+            ("town1", Faction::Town, Role::Vanilla),
+            ("town2", Faction::Town, Role::Vanilla),
+            ("town3", Faction::Town, Role::Vanilla),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        // the game goes on without them
+        assert_eq!(lynch(&mut world, ids[0]), (false, vec![ids[0]]));
+    }
+
+    #[test]
+    fn survivor_wins_alive_at_the_end() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("survivor", Faction::Survivor, Role::Survivor),
+            ("town1", Faction::Town, Role::Vanilla),
+            ("town2", Faction::Town, Role::Vanilla),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        assert_eq!(lynch(&mut world, ids[3]), (true, vec![ids[0], ids[1], ids[2]]));
+    }
+
+    #[test]
+    fn dead_survivor_does_not_win() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("survivor", Faction::Survivor, Role::Survivor),
+            ("town1", Faction::Town, Role::Vanilla),
+            ("town2", Faction::Town, Role::Vanilla),
+            ("town3", Faction::Town, Role::Vanilla),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        assert_eq!(lynch(&mut world, ids[0]), (false, Vec::new()));
+        assert_eq!(lynch(&mut world, ids[4]), (true, vec![ids[1], ids[2], ids[3]]));
+    }
+
+    #[test]
+    fn nobody_wins_when_everyone_dies() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("goon", Faction::Mafia(0), Role::Goon),
+            ("bomb", Faction::Town, Role::Bomb),
+        ]);
+        kill_player(&mut world, ids[1], DeathCause::MafiaKill, Some(ids[0]));
+        kill_player(&mut world, ids[0], DeathCause::Bomb, Some(ids[1]));
+        CheckWinConditions.run_now(&world);
+        let winners = world.read_resource::<Winners>();
+        assert!(winners.game_over);
+        assert!(winners.players.is_empty());
+    }
+
+    #[test]
+    fn survivors_alone_end_the_game_in_a_draw() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("survivor", Faction::Survivor, Role::Survivor),
+            ("town", Faction::Town, Role::Vanilla),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        kill_player(&mut world, ids[1], DeathCause::MafiaKill, Some(ids[2]));
+        assert_eq!(lynch(&mut world, ids[2]), (true, vec![ids[0]]));
+    }
+
+    /// Use every action the player has on the target tonight
+    fn target(world: &mut World, player: Entity, target: Entity) {
+        world.write_storage::<Target>().insert(player, Target(Some(target))).unwrap();
+        for kind in ActionKind::ALL.iter() {
+            set_action(world, player, *kind, true, Some(target));
+        }
+    }
+
+    fn succeeded(world: &World, player: Entity) -> bool {
+        world.read_storage::<NightResult>().get(player).unwrap().success
+    }
+
+    #[test]
+    fn framed_players_appear_guilty() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("framer", Faction::Mafia(0), Role::Framer),
+            ("town", Faction::Town, Role::Vanilla),
+        ]);
+        target(&mut world, ids[0], ids[1]);
+        FrameActions.run_now(&world);
+        assert!(succeeded(&world, ids[0]));
+        let apparent = world.read_storage::<Apparent<Innocence>>();
+        assert_eq!(apparent.get(ids[1]).map(|apparent| &apparent.0), Some(&Innocence::Guilty));
+    }
+
+    #[test]
+    fn blocked_framers_frame_nobody() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("framer", Faction::Mafia(0), Role::Framer),
+            ("town", Faction::Town, Role::Vanilla),
+        ]);
+        target(&mut world, ids[0], ids[1]);
+        world.write_storage::<Blocked>().insert(ids[0], Blocked).unwrap();
+        FrameActions.run_now(&world);
+        assert!(!succeeded(&world, ids[0]));
+        assert!(world.read_storage::<Apparent<Innocence>>().get(ids[1]).is_none());
+    }
+
+    #[test]
+    fn janitors_clean_tonights_kills() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("janitor", Faction::Mafia(0), Role::Janitor),
+            ("cop", Faction::Town, Role::Cop),
+        ]);
+        target(&mut world, ids[0], ids[1]);
+        let dead = Dead { night: Night(0), phase: Phase::Night, cause: DeathCause::MafiaKill, killer: None };
+        world.write_storage::<Dead>().insert(ids[1], dead).unwrap();
+        CleanActions.run_now(&world);
+        assert!(succeeded(&world, ids[0]));
+        assert!(world.read_storage::<Cleaned>().contains(ids[1]));
+        let knowledge = world.read_storage::<Knowledge>();
+        assert_eq!(knowledge.get(ids[0]).unwrap().role_of(ids[1]), Some(&Role::Cop));
+    }
+
+    #[test]
+    fn janitors_cannot_clean_the_living() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("janitor", Faction::Mafia(0), Role::Janitor),
+            ("cop", Faction::Town, Role::Cop),
+        ]);
+        target(&mut world, ids[0], ids[1]);
+        CleanActions.run_now(&world);
+        assert!(!succeeded(&world, ids[0]));
+        assert!(!world.read_storage::<Cleaned>().contains(ids[1]));
+    }
+
+    #[test]
+    fn night_kills_record_their_killer() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("goon", Faction::Mafia(0), Role::Goon),
+            ("town", Faction::Town, Role::Vanilla),
+        ]);
+        world.write_resource::<CurrentNight>().0 = Night(1);
+        target(&mut world, ids[0], ids[1]);
+        KillActions.run_now(&world);
+        let dead = world.read_storage::<Dead>();
+        let died = dead.get(ids[1]).unwrap();
+        assert_eq!(died.cause, DeathCause::MafiaKill);
+        assert_eq!(died.killer, Some(ids[0]));
+    }
+
+    #[test]
+    fn killing_a_bomb_kills_the_killer() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("goon", Faction::Mafia(0), Role::Goon),
+            ("bomb", Faction::Town, Role::Bomb),
+        ]);
+        world.write_resource::<CurrentNight>().0 = Night(1);
+        target(&mut world, ids[0], ids[1]);
+        KillActions.run_now(&world);
+        let dead = world.read_storage::<Dead>();
+        let died = dead.get(ids[0]).unwrap();
+        assert_eq!(died.cause, DeathCause::Bomb);
+        assert_eq!(died.killer, Some(ids[1]));
+    }
+
+    fn save_on(world: &mut World, night: u8, doctor: Entity, player: Entity) -> bool {
+        world.write_resource::<CurrentNight>().0 = Night(night);
+        world.write_storage::<Saved>().clear();
+        target(world, doctor, player);
+        SaveActions.run_now(world);
+        world.read_storage::<Saved>().contains(player)
+    }
+
+    #[test]
+    fn doctors_cannot_self_save_when_forbidden() {
+        let mut world = world();
+        let ids = players(&mut world, &[("doctor", Faction::Town, Role::Doctor)]);
+        world.write_resource::<DoctorRules>().self_save = false;
+        assert!(!save_on(&mut world, 1, ids[0], ids[0]));
+        assert!(!succeeded(&world, ids[0]));
+    }
+
+    #[test]
+    fn doctors_cannot_save_the_same_player_twice_in_a_row_when_forbidden() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("doctor", Faction::Town, Role::Doctor),
+            ("town1", Faction::Town, Role::Vanilla),
+            ("town2", Faction::Town, Role::Vanilla),
+        ]);
+        world.write_resource::<DoctorRules>().consecutive_saves = false;
+        assert!(save_on(&mut world, 1, ids[0], ids[1]));
+        assert!(!save_on(&mut world, 2, ids[0], ids[1]));
+        assert!(save_on(&mut world, 3, ids[0], ids[2]));
+        assert!(save_on(&mut world, 4, ids[0], ids[1]));
+    }
+
+    #[test]
+    fn doctors_run_out_of_self_saves() {
+        let mut world = world();
+        let ids = players(&mut world, &[("doctor", Faction::Town, Role::Doctor)]);
+        world.write_resource::<DoctorRules>().max_self_saves = Some(1);
+        assert!(save_on(&mut world, 1, ids[0], ids[0]));
+        assert!(!save_on(&mut world, 2, ids[0], ids[0]));
+    }
+
+    fn knows_armed(world: &World, player: Entity, target: Entity) -> Option<bool> {
+        world.read_storage::<Knowledge>().get(player).unwrap().facts().iter()
+            .find_map(|fact| match fact {
+                Fact::Armed(armed, is_armed) if *armed == target => Some(*is_armed),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn gunsmiths_find_guns() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("gunsmith1", Faction::Town, Role::Gunsmith),
+            ("gunsmith2", Faction::Town, Role::Gunsmith),
+            ("owner", Faction::Town, Role::GunOwner),
+            ("vanilla", Faction::Town, Role::Vanilla),
+        ]);
+        target(&mut world, ids[0], ids[2]);
+        target(&mut world, ids[1], ids[3]);
+        GunCheckActions.run_now(&world);
+        assert_eq!(knows_armed(&world, ids[0], ids[2]), Some(true));
+        assert_eq!(knows_armed(&world, ids[1], ids[3]), Some(false));
+    }
+
+    #[test]
+    fn paranoid_gunsmiths_see_guns_everywhere() {
+        let mut world = world();
+        let gunsmith = create_player(&mut world, String::from("gunsmith"), Faction::Town, Role::Gunsmith, vec![Modifier::Paranoid]);
+        let vanilla = create_player(&mut world, String::from("vanilla"), Faction::Town, Role::Vanilla, Vec::new());
+        target(&mut world, gunsmith, vanilla);
+        GunCheckActions.run_now(&world);
+        assert_eq!(knows_armed(&world, gunsmith, vanilla), Some(true));
+    }
+
+    #[test]
+    fn gun_dealers_arm_their_target() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("dealer", Faction::Town, Role::GunDealer),
+            ("vanilla", Faction::Town, Role::Vanilla),
+        ]);
+        target(&mut world, ids[0], ids[1]);
+        GiveGunActions.run_now(&world);
+        assert!(succeeded(&world, ids[0]));
+        assert!(world.read_storage::<Gun>().contains(ids[1]));
+        assert!(world.read_storage::<actions::Shoot>().contains(ids[1]));
+    }
+
+    #[test]
+    fn blocked_gun_dealers_arm_nobody() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("dealer", Faction::Town, Role::GunDealer),
+            ("vanilla", Faction::Town, Role::Vanilla),
+        ]);
+        target(&mut world, ids[0], ids[1]);
+        world.write_storage::<Blocked>().insert(ids[0], Blocked).unwrap();
+        GiveGunActions.run_now(&world);
+        assert!(!succeeded(&world, ids[0]));
+        assert!(!world.read_storage::<Gun>().contains(ids[1]));
+    }
+
+    /// Use the player's action of the kind on the target tonight, as `Game` records it
+    fn visit(world: &mut World, player: Entity, kind: ActionKind, visited: Entity) {
+        world.write_resource::<SubmittedActions>().0.push(Submitted { player, kind, target: Some(visited) });
+        target(world, player, visited);
+    }
+
+    fn val(world: &World, player: Entity) -> String {
+        world.read_storage::<NightResult>().get(player).unwrap().val.clone()
+    }
+
+    #[test]
+    fn trackers_and_watchers_see_the_visit_graph() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("tracker", Faction::Town, Role::Tracker),
+            ("watcher", Faction::Town, Role::Watcher),
+            ("cop", Faction::Town, Role::Cop),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        visit(&mut world, ids[0], ActionKind::Track, ids[2]);
+        visit(&mut world, ids[1], ActionKind::Watch, ids[3]);
+        visit(&mut world, ids[2], ActionKind::Cop, ids[3]);
+        UpdateVisits.run_now(&world);
+        TrackActions.run_now(&world);
+        WatchActions.run_now(&world);
+        let graph = world.read_resource::<VisitGraph>();
+        assert_eq!(graph.visited_by(ids[2]), vec![ids[3]]);
+        assert_eq!(graph.visitors_of(ids[3]), vec![ids[1], ids[2]]);
+        drop(graph);
+        assert_eq!(val(&world, ids[0]), "goon");
+        // watchers do not see themselves
+        assert_eq!(val(&world, ids[1]), "cop");
+    }
+
+    #[test]
+    fn blocked_visitors_never_arrive() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("watcher", Faction::Town, Role::Watcher),
+            ("cop", Faction::Town, Role::Cop),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        visit(&mut world, ids[0], ActionKind::Watch, ids[2]);
+        visit(&mut world, ids[1], ActionKind::Cop, ids[2]);
+        world.write_storage::<Blocked>().insert(ids[1], Blocked).unwrap();
+        UpdateVisits.run_now(&world);
+        WatchActions.run_now(&world);
+        let graph = world.read_resource::<VisitGraph>();
+        assert!(graph.visits.iter().any(|visit| visit.visitor == ids[1] && visit.blocked));
+        assert!(graph.visited_by(ids[1]).is_empty());
+        drop(graph);
+        assert_eq!(val(&world, ids[0]), "");
+    }
+
+    #[test]
+    fn astral_visitors_are_never_seen() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("tracker", Faction::Town, Role::Tracker),
+            ("cop", Faction::Town, Role::Cop),
+            ("goon", Faction::Mafia(0), Role::Goon),
+        ]);
+        world.write_storage::<Astral>().insert(ids[1], Astral).unwrap();
+        visit(&mut world, ids[0], ActionKind::Track, ids[1]);
+        visit(&mut world, ids[1], ActionKind::Cop, ids[2]);
+        UpdateVisits.run_now(&world);
+        TrackActions.run_now(&world);
+        assert!(world.read_resource::<VisitGraph>().visitors_of(ids[2]).is_empty());
+        assert!(succeeded(&world, ids[0]));
+        assert_eq!(val(&world, ids[0]), "");
+    }
+
+    #[test]
+    fn blocked_killers_kill_nobody() {
+        let mut world = world();
+        let ids = players(&mut world, &[
+            ("goon1", Faction::Mafia(0), Role::Goon),
+            ("goon2", Faction::Mafia(0), Role::Goon),
+            ("town1", Faction::Town, Role::Vanilla),
+            ("town2", Faction::Town, Role::Vanilla),
+        ]);
+        world.write_resource::<CurrentNight>().0 = Night(1);
+        target(&mut world, ids[0], ids[2]);
+        target(&mut world, ids[1], ids[3]);
+        world.write_storage::<Blocked>().insert(ids[0], Blocked).unwrap();
+        KillActions.run_now(&world);
+        assert!(!succeeded(&world, ids[0]));
+        // the blocked goon still used up the team's kill
+        assert!(!succeeded(&world, ids[1]));
+        let dead = world.read_storage::<Dead>();
+        assert!(!dead.contains(ids[2]));
+        assert!(!dead.contains(ids[3]));
+        assert!(world.read_resource::<VisitGraph>().kills.is_empty());
     }
 }
@@ -0,0 +1,226 @@
+use std::fmt;
+use specs::{World, WorldExt, Entity};
+use super::components::{Name, Dead, Charges, SaveHistory};
+use super::components::actions::{ActionKind, with_action};
+use super::resources::{CurrentNight, CurrentPhase, Phase, StartRules, DoctorRules};
+use super::catalogue::{english, error_key};
+
+/// Why a submitted action was rejected
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActionError {
+    /// The target is dead
+    DeadTarget,
+    /// The action may not be used on the player using it
+    SelfTarget,
+    /// The player has no uses of the action left
+    NoCharges,
+    /// The action may not be used in the current phase, or on night 0
+    WrongPhase,
+    /// The target was also chosen on the previous night
+    ConsecutiveTarget,
+    /// The player or the target is not in the game
+    UnknownPlayer,
+    /// The player using the action is dead
+    DeadPlayer,
+    /// The player does not have the action
+    MissingAction,
+    /// Someone has already been lynched today
+    AlreadyLynched,
+}
+
+/// Shows the English text, see `Catalogue::action_error` for the game's own language
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", english(&error_key(self)))
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// Check that the player may use the action on the target right now
+/// Every submitted action passes through here before it is stored
+pub fn validate(world: &World, player: Entity, kind: ActionKind, target: Option<Entity>) -> Result<(), ActionError> {
+    let names = world.read_storage::<Name>();
+    let dead = world.read_storage::<Dead>();
+    let is_player = |ent: Entity| world.entities().is_alive(ent) && names.get(ent).is_some();
+
+    if !is_player(player) {
+        return Err(ActionError::UnknownPlayer);
+    }
+    if dead.get(player).is_some() {
+        return Err(ActionError::DeadPlayer);
+    }
+    if !has_action(world, player, kind) {
+        return Err(ActionError::MissingAction);
+    }
+    if world.read_storage::<Charges>().get(player).and_then(|charges| charges.left(kind)) == Some(0) {
+        return Err(ActionError::NoCharges);
+    }
+
+    let phase = world.read_resource::<CurrentPhase>();
+    let night = world.read_resource::<CurrentNight>();
+    let rules = world.read_resource::<StartRules>();
+    let right_phase = if kind.day_action() {
+        phase.0 == Phase::Day
+    } else {
+        phase.0 == Phase::Night && rules.allows(&night.0, kind)
+    };
+    if !right_phase {
+        return Err(ActionError::WrongPhase);
+    }
+
+    if let Some(target) = target.filter(|_| kind.targeted()) {
+        if !is_player(target) {
+            return Err(ActionError::UnknownPlayer);
+        }
+        if dead.get(target).is_some() {
+            return Err(ActionError::DeadTarget);
+        }
+        if target == player && !kind.self_targetable() {
+            return Err(ActionError::SelfTarget);
+        }
+        if kind == ActionKind::Save {
+            let history = world.read_storage::<SaveHistory>();
+            world.read_resource::<DoctorRules>().check(player, target, history.get(player), &night.0)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the player has an action of the kind
+pub fn has_action(world: &World, player: Entity, kind: ActionKind) -> bool {
+    with_action!(kind, A => world.read_storage::<A>().get(player).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{World, WorldExt, Entity, Builder, RunNow};
+    use super::ActionError;
+    use crate::components::{Faction, Role};
+    use crate::components::actions::ActionKind;
+    use crate::entities::{create_player, lynch_player, submit_action};
+    use crate::game::{Game, PlayerId, Setup};
+    use crate::resources::{CurrentNight, CurrentPhase, Night, Phase, StartRules, DoctorRules};
+    use crate::systems::SaveActions;
+    use crate::systems::tests::world;
+
+    /// A world on night 0 with a cop, a doctor, a goon and a vanilla
+    fn game() -> (World, Vec<Entity>) {
+        let mut world = world();
+        world.insert(StartRules::default());
+        world.insert(CurrentNight(Night(0)));
+        world.insert(CurrentPhase(Phase::Night));
+        let ids = [
+            ("cop", Faction::Town, Role::Cop),
+            ("doctor", Faction::Town, Role::Doctor),
+            ("goon", Faction::Mafia(0), Role::Goon),
+            ("vanilla", Faction::Town, Role::Vanilla),
+        ].iter()
+            .map(|(name, faction, role)| create_player(&mut world, String::from(*name), faction.clone(), role.clone(), Vec::new()))
+            .collect();
+        (world, ids)
+    }
+
+    fn night(world: &mut World, night: u8) {
+        world.write_resource::<CurrentNight>().0 = Night(night);
+        world.write_resource::<CurrentPhase>().0 = Phase::Night;
+    }
+
+    #[test]
+    fn dead_target() {
+        let (mut world, ids) = game();
+        lynch_player(&mut world, ids[3]);
+        assert_eq!(submit_action(&mut world, ids[0], ActionKind::Cop, Some(ids[3])), Err(ActionError::DeadTarget));
+    }
+
+    #[test]
+    fn self_target() {
+        let (mut world, ids) = game();
+        assert_eq!(submit_action(&mut world, ids[0], ActionKind::Cop, Some(ids[0])), Err(ActionError::SelfTarget));
+    }
+
+    #[test]
+    fn no_charges() {
+        let (mut world, ids) = game();
+        world.insert(DoctorRules { max_self_saves: Some(1), ..DoctorRules::default() });
+        submit_action(&mut world, ids[1], ActionKind::Save, Some(ids[1])).unwrap();
+        SaveActions.run_now(&world);
+        night(&mut world, 1);
+        assert_eq!(submit_action(&mut world, ids[1], ActionKind::Save, Some(ids[1])), Err(ActionError::NoCharges));
+    }
+
+    #[test]
+    fn wrong_phase() {
+        let (mut world, ids) = game();
+        // no kills on night 0
+        assert_eq!(submit_action(&mut world, ids[2], ActionKind::Kill, Some(ids[0])), Err(ActionError::WrongPhase));
+        world.write_resource::<CurrentPhase>().0 = Phase::Day;
+        assert_eq!(submit_action(&mut world, ids[0], ActionKind::Cop, Some(ids[2])), Err(ActionError::WrongPhase));
+        night(&mut world, 1);
+        assert_eq!(submit_action(&mut world, ids[2], ActionKind::Kill, Some(ids[0])), Ok(()));
+    }
+
+    #[test]
+    fn consecutive_target() {
+        let (mut world, ids) = game();
+        world.insert(DoctorRules { consecutive_saves: false, ..DoctorRules::default() });
+        submit_action(&mut world, ids[1], ActionKind::Save, Some(ids[0])).unwrap();
+        SaveActions.run_now(&world);
+        night(&mut world, 1);
+        assert_eq!(submit_action(&mut world, ids[1], ActionKind::Save, Some(ids[0])), Err(ActionError::ConsecutiveTarget));
+    }
+
+    #[test]
+    fn unknown_player() {
+        let (mut world, ids) = game();
+        let stranger = world.create_entity().build();
+        assert_eq!(submit_action(&mut world, ids[0], ActionKind::Cop, Some(stranger)), Err(ActionError::UnknownPlayer));
+        assert_eq!(submit_action(&mut world, stranger, ActionKind::Cop, Some(ids[2])), Err(ActionError::UnknownPlayer));
+    }
+
+    #[test]
+    fn dead_player() {
+        let (mut world, ids) = game();
+        lynch_player(&mut world, ids[0]);
+        assert_eq!(submit_action(&mut world, ids[0], ActionKind::Cop, Some(ids[2])), Err(ActionError::DeadPlayer));
+    }
+
+    #[test]
+    fn missing_action() {
+        let (mut world, ids) = game();
+        assert_eq!(submit_action(&mut world, ids[3], ActionKind::Cop, Some(ids[2])), Err(ActionError::MissingAction));
+    }
+
+    #[test]
+    fn already_lynched() {
+        let mut game = Game::new(Setup::default());
+        let players: Vec<PlayerId> = ["v1", "v2", "v3", "v4"].iter()
+            .map(|name| game.add_player(name, Faction::Town, Role::Vanilla, Vec::new()))
+            .collect();
+        game.add_player("goon", Faction::Mafia(0), Role::Goon, Vec::new());
+        game.advance_phase();
+        game.lynch(players[0]).unwrap();
+        assert_eq!(game.lynch(players[1]), Err(ActionError::AlreadyLynched));
+        game.advance_phase();
+        game.advance_phase();
+        assert_eq!(game.lynch(players[1]), Ok(()));
+    }
+
+    #[test]
+    fn kills_are_allowed_on_night_one_without_a_night_zero() {
+        let day_start = StartRules { day_start: true, ..StartRules::default() };
+        let night_one_start = StartRules { night_zero: false, ..StartRules::default() };
+        for start in [day_start, night_one_start] {
+            let mut game = Game::new(Setup { start, ..Setup::default() });
+            let goon = game.add_player("goon", Faction::Mafia(0), Role::Goon, Vec::new());
+            let players: Vec<PlayerId> = ["v1", "v2", "v3", "v4"].iter()
+                .map(|name| game.add_player(name, Faction::Town, Role::Vanilla, Vec::new()))
+                .collect();
+            if game.phase() == Phase::Day {
+                game.advance_phase();
+            }
+            assert_eq!(game.state().night, Night(1));
+            assert_eq!(game.submit_action(goon, ActionKind::Kill, Some(players[0])), Ok(()));
+        }
+    }
+}
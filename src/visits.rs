@@ -0,0 +1,190 @@
+use specs::{World, WorldExt, Entity};
+use serde::Serialize;
+use super::catalogue::{Catalogue, cause_key};
+use super::components::{Name, Dead};
+use super::components::actions::ActionKind;
+use super::resources::{Night, Phase, VisitGraph};
+
+/// A visit with both players named
+#[derive(Clone, Debug, Serialize)]
+pub struct NamedVisit {
+    pub visitor: String,
+    pub target: String,
+    pub action: ActionKind,
+    pub blocked: bool,
+}
+
+/// A kill with both players named, and its cause as shown in the graveyard
+#[derive(Clone, Debug, Serialize)]
+pub struct NamedKill {
+    pub killer: String,
+    pub victim: String,
+    pub cause: String,
+}
+
+/// A night's visit graph with every player named, for post-game reviews
+/// Players are everyone alive during the night, so that those who went unvisited still appear
+#[derive(Clone, Debug, Serialize)]
+pub struct VisitExport {
+    pub night: Night,
+    pub players: Vec<String>,
+    pub visits: Vec<NamedVisit>,
+    pub kills: Vec<NamedKill>,
+}
+
+fn name_of(world: &World, player: Entity) -> String {
+    world.read_storage::<Name>().get(player)
+        .map_or_else(|| format!("{:?}", player), |name| name.0.clone())
+}
+
+/// Name everything in the last night's `VisitGraph`
+pub fn export(world: &World) -> VisitExport {
+    use specs::Join;
+
+    let graph = world.read_resource::<VisitGraph>();
+    let catalogue = world.read_resource::<Catalogue>();
+    let players = (&world.read_storage::<Name>(), (&world.read_storage::<Dead>()).maybe()).join()
+        // anyone killed by day died before the night they are recorded on
+        .filter(|(_, dead)| dead.is_none_or(|dead| match dead.phase {
+            Phase::Night => dead.night.0 >= graph.night.0,
+            Phase::Day   => dead.night.0 > graph.night.0,
+        }))
+        .map(|(name, _)| name.0.clone())
+        .collect();
+    VisitExport {
+        night: graph.night.clone(),
+        players,
+        visits: graph.visits.iter()
+            .map(|visit| NamedVisit {
+                visitor: name_of(world, visit.visitor),
+                target: name_of(world, visit.target),
+                action: visit.action,
+                blocked: visit.blocked,
+            })
+            .collect(),
+        kills: graph.kills.iter()
+            .map(|kill| NamedKill {
+                killer: name_of(world, kill.killer),
+                victim: name_of(world, kill.victim),
+                cause: catalogue.render(&cause_key(&kill.cause), &[]),
+            })
+            .collect(),
+    }
+}
+
+/// Quote a name for use as a Graphviz id
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl VisitExport {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Graphviz source for the night, e.g. for `dot -Tpng`
+    /// Blocked visits are dashed and grey, kills are red and bold, and victims are filled in
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph night_{} {{\n    label=\"Night {}\";\n", self.night.0, self.night.0);
+        for player in self.players.iter() {
+            if self.kills.iter().any(|kill| kill.victim == *player) {
+                dot.push_str(&format!("    {} [style=filled, fillcolor=lightgrey];\n", quote(player)));
+            } else {
+                dot.push_str(&format!("    {};\n", quote(player)));
+            }
+        }
+        for visit in self.visits.iter() {
+            let style = if visit.blocked { ", style=dashed, color=grey" } else { "" };
+            dot.push_str(&format!("    {} -> {} [label=\"{:?}\"{}];\n",
+                quote(&visit.visitor), quote(&visit.target), visit.action, style));
+        }
+        for kill in self.kills.iter() {
+            dot.push_str(&format!("    {} -> {} [label={}, color=red, penwidth=2];\n",
+                quote(&kill.killer), quote(&kill.victim), quote(&kill.cause)));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::{Faction, Role};
+    use crate::components::actions::ActionKind;
+    use crate::game::{Game, Setup};
+
+    /// Night 1 with a goon killing a vanilla, a blocked cop, and a roleblocker and tracker who visit
+    fn night_one() -> Game {
+        let mut game = Game::new(Setup::default());
+        let goon = game.add_player("goon", Faction::Mafia(0), Role::Goon, Vec::new());
+        let blocker = game.add_player("blocker", Faction::Mafia(0), Role::Roleblocker, Vec::new());
+        let cop = game.add_player("cop", Faction::Town, Role::Cop, Vec::new());
+        let tracker = game.add_player("tracker", Faction::Town, Role::Tracker, Vec::new());
+        let vanilla = game.add_player("vanilla", Faction::Town, Role::Vanilla, Vec::new());
+        game.add_player("idle", Faction::Town, Role::Vanilla, Vec::new());
+        game.advance_phase();
+        game.advance_phase();
+        game.submit_action(goon, ActionKind::Kill, Some(vanilla)).unwrap();
+        game.submit_action(blocker, ActionKind::Block, Some(cop)).unwrap();
+        game.submit_action(cop, ActionKind::Cop, Some(goon)).unwrap();
+        game.submit_action(tracker, ActionKind::Track, Some(goon)).unwrap();
+        game.advance_phase();
+        game
+    }
+
+    #[test]
+    fn export_names_every_visit_and_kill() {
+        let export = night_one().visit_export();
+        assert_eq!(export.night.0, 1);
+        assert_eq!(export.players, vec!["goon", "blocker", "cop", "tracker", "vanilla", "idle"]);
+        let cop = export.visits.iter().find(|visit| visit.visitor == "cop").unwrap();
+        assert_eq!(cop.target, "goon");
+        assert!(cop.blocked);
+        let tracker = export.visits.iter().find(|visit| visit.visitor == "tracker").unwrap();
+        assert!(!tracker.blocked);
+        assert_eq!(export.kills.len(), 1);
+        assert_eq!(export.kills[0].killer, "goon");
+        assert_eq!(export.kills[0].victim, "vanilla");
+    }
+
+    #[test]
+    fn players_lynched_the_day_before_are_left_out() {
+        let mut game = Game::new(Setup::default());
+        let goon = game.add_player("goon", Faction::Mafia(0), Role::Goon, Vec::new());
+        let lynched = game.add_player("lynched", Faction::Town, Role::Vanilla, Vec::new());
+        let vanilla = game.add_player("vanilla", Faction::Town, Role::Vanilla, Vec::new());
+        for name in ["v1", "v2", "v3"].iter() {
+            game.add_player(name, Faction::Town, Role::Vanilla, Vec::new());
+        }
+        game.advance_phase();
+        game.lynch(lynched).unwrap();
+        game.advance_phase();
+        game.submit_action(goon, ActionKind::Kill, Some(vanilla)).unwrap();
+        game.advance_phase();
+        let export = game.visit_export();
+        assert_eq!(export.night.0, 1);
+        assert_eq!(export.players, vec!["goon", "vanilla", "v1", "v2", "v3"]);
+        assert!(!export.to_dot().contains("\"lynched\""));
+    }
+
+    #[test]
+    fn dot_styles_blocked_visits_and_kills() {
+        let dot = night_one().visit_export().to_dot();
+        assert!(dot.starts_with("digraph night_1 {"));
+        assert!(dot.contains("    \"idle\";\n"));
+        assert!(dot.contains("    \"vanilla\" [style=filled, fillcolor=lightgrey];\n"));
+        assert!(dot.contains("    \"cop\" -> \"goon\" [label=\"Cop\", style=dashed, color=grey];\n"));
+        assert!(dot.contains("    \"tracker\" -> \"goon\" [label=\"Track\"];\n"));
+        assert!(dot.contains("    \"goon\" -> \"vanilla\" [label="));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn json_lists_players_visits_and_kills() {
+        let json = night_one().visit_export().to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["players"].as_array().unwrap().len(), 6);
+        assert_eq!(value["visits"].as_array().unwrap().len(), 4);
+        assert_eq!(value["kills"][0]["victim"], "vanilla");
+    }
+}